@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use std::fmt;
+use utoipa::ToSchema;
+use crate::representation::ATTRIBUTE_MEMBERS;
+
+/// Decimal places kept in GeoProperty coordinates, about a centimetre.
+pub const COORDINATE_PRECISION: i32 = 7;
+
+/// NGSI-LD attribute types.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, PartialEq)]
+pub enum AttributeType {
+    Property,
+    Relationship,
+    GeoProperty,
+    LanguageProperty,
+    ListProperty,
+    ListRelationship,
+    VocabProperty,
+    JsonProperty,
+}
+
+impl AttributeType {
+    /// Type of a normalized attribute, `None` for anything else.
+    pub fn of(attribute: &Value) -> Option<Self> {
+        match attribute.get("type")?.as_str()? {
+            "Property" => Some(AttributeType::Property),
+            "Relationship" => Some(AttributeType::Relationship),
+            "GeoProperty" => Some(AttributeType::GeoProperty),
+            "LanguageProperty" => Some(AttributeType::LanguageProperty),
+            "ListProperty" => Some(AttributeType::ListProperty),
+            "ListRelationship" => Some(AttributeType::ListRelationship),
+            "VocabProperty" => Some(AttributeType::VocabProperty),
+            "JsonProperty" => Some(AttributeType::JsonProperty),
+            _ => None,
+        }
+    }
+}
+
+/// Attribute values without a canonical form.
+#[derive(Debug, PartialEq)]
+pub enum AttributeError {
+    /// Two tags of a `languageMap` only differ by case.
+    DuplicateLanguageTag(String),
+}
+
+impl fmt::Display for AttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeError::DuplicateLanguageTag(tag) => write!(f, "language tag '{}' appears more than once in the languageMap", tag),
+        }
+    }
+}
+
+impl std::error::Error for AttributeError {}
+
+/// Brings the values of an attribute, its sub-attributes, the instances of a
+/// multi-attribute or the attributes of an entity to their canonical form
+/// before they are signed or verified, so that equivalent values written
+/// differently carry the same proof:
+///
+/// - GeoProperty coordinates are rounded to [`COORDINATE_PRECISION`] decimals
+/// - LanguageProperty language tags get the BCP 47 case (`en-GB`, `zh-Hant`);
+///   tags that only differ by case are rejected
+/// - VocabProperty IRIs get a lowercase scheme
+/// - a ListProperty `valueList` given as a single value is a one-value list
+///
+/// The previous value of a notification gets the same canonical form as the
+/// value. Relationship and ListRelationship objects are entity ids, which
+/// brokers compare as exact strings, and are left as they are.
+pub fn canonicalize(value: &Value) -> Result<Value, AttributeError> {
+    match value {
+        Value::Array(items) => Ok(Value::Array(items.iter().map(canonicalize).collect::<Result<_, _>>()?)),
+        Value::Object(obj) => match AttributeType::of(value) {
+            Some(attribute_type) => Ok(Value::Object(obj.iter()
+                .map(|(key, member)| {
+                    let member = match ATTRIBUTE_MEMBERS.contains(&key.as_str()) {
+                        true => canonical_member(attribute_type, key, member)?,
+                        false => canonicalize(member)?,
+                    };
+                    Ok((key.clone(), member))
+                })
+                .collect::<Result<_, AttributeError>>()?)),
+            // An entity, whose attributes are canonicalized
+            None => Ok(Value::Object(obj.iter()
+                .map(|(key, member)| Ok((key.clone(), canonicalize(member)?)))
+                .collect::<Result<_, AttributeError>>()?)),
+        },
+        other => Ok(other.clone()),
+    }
+}
+
+fn canonical_member(attribute_type: AttributeType, key: &str, member: &Value) -> Result<Value, AttributeError> {
+    Ok(match (attribute_type, key) {
+        (AttributeType::GeoProperty, "value" | "previousValue") => canonical_geometry(member),
+        (AttributeType::LanguageProperty, "languageMap" | "previousLanguageMap") => match member {
+            Value::Object(map) => {
+                let mut canonical = Map::new();
+                for (tag, text) in map {
+                    let tag = canonical_language_tag(tag);
+                    if canonical.contains_key(&tag) {
+                        return Err(AttributeError::DuplicateLanguageTag(tag));
+                    }
+                    canonical.insert(tag, text.clone());
+                }
+                Value::Object(canonical)
+            }
+            other => other.clone(),
+        },
+        (AttributeType::VocabProperty, "vocab" | "previousVocab") => map_strings(member, canonical_iri),
+        (AttributeType::ListProperty, "valueList" | "previousValueList") => match member {
+            Value::Array(_) => member.clone(),
+            other => Value::Array(vec![other.clone()]),
+        },
+        _ => member.clone(),
+    })
+}
+
+/// Applies `f` to a string, or to each string of an array.
+fn map_strings(value: &Value, f: fn(&str) -> String) -> Value {
+    match value {
+        Value::String(s) => Value::String(f(s)),
+        Value::Array(items) => Value::Array(items.iter().map(|item| map_strings(item, f)).collect()),
+        other => other.clone(),
+    }
+}
+
+fn canonical_geometry(geometry: &Value) -> Value {
+    let Value::Object(obj) = geometry else {
+        return geometry.clone();
+    };
+
+    Value::Object(obj.iter()
+        .map(|(key, member)| match key.as_str() {
+            "coordinates" => (key.clone(), round_coordinates(member)),
+            "geometries" => (key.clone(), match member {
+                Value::Array(geometries) => Value::Array(geometries.iter().map(canonical_geometry).collect()),
+                other => other.clone(),
+            }),
+            _ => (key.clone(), member.clone()),
+        })
+        .collect())
+}
+
+fn round_coordinates(coordinates: &Value) -> Value {
+    match coordinates {
+        Value::Array(items) => Value::Array(items.iter().map(round_coordinates).collect()),
+        Value::Number(n) if n.is_f64() => {
+            let scale = 10f64.powi(COORDINATE_PRECISION);
+            n.as_f64()
+                .and_then(|x| Number::from_f64((x * scale).round() / scale))
+                .map_or_else(|| coordinates.clone(), Value::Number)
+        }
+        other => other.clone(),
+    }
+}
+
+/// BCP 47 case of a language tag: lowercase language, titlecase script,
+/// uppercase region, and lowercase from the first singleton on.
+fn canonical_language_tag(tag: &str) -> String {
+    if tag.starts_with('@') {
+        return tag.to_string();
+    }
+
+    let mut after_singleton = false;
+    tag.split('-')
+        .enumerate()
+        .map(|(i, subtag)| {
+            let canonical = match subtag.len() {
+                _ if i == 0 || after_singleton => subtag.to_ascii_lowercase(),
+                2 => subtag.to_ascii_uppercase(),
+                4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    let (first, rest) = subtag.split_at(1);
+                    first.to_ascii_uppercase() + &rest.to_ascii_lowercase()
+                }
+                _ => subtag.to_ascii_lowercase(),
+            };
+            after_singleton |= subtag.len() == 1;
+            canonical
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// IRI with a lowercase scheme, which is case-insensitive. Compact IRIs,
+/// terms and URNs are returned unchanged.
+fn canonical_iri(iri: &str) -> String {
+    match iri.split_once(':') {
+        Some((scheme, rest)) if rest.starts_with("//") => format!("{}:{}", scheme.to_ascii_lowercase(), rest),
+        _ => iri.to_string(),
+    }
+}
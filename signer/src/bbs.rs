@@ -0,0 +1,505 @@
+//! BBS signatures (draft-irtf-cfrg-bbs-signatures) with the
+//! `BLS12-381-SHA-256` ciphersuite: multi-message signatures and zero-knowledge
+//! proofs of knowledge disclosing a subset of the signed messages.
+
+use bls12_381::hash_to_curve::{ExpandMessageState, HashToCurve, InitExpandMessage};
+use bls12_381::{multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// `ciphersuite_id || "H2G_HM2S_"`: messages are hashed to scalars.
+const API_ID: &[u8] = b"BBS_BLS12381G1_XMD:SHA-256_SSWU_RO_H2G_HM2S_";
+
+/// Length of the expanded messages hashed to scalars and generators.
+const EXPAND_LEN: usize = 48;
+
+const POINT_LEN: usize = 48;
+const SCALAR_LEN: usize = 32;
+
+/// Most messages signed together. One generator is hashed to the curve per
+/// message, so the count read from a submitted proof must be bounded before
+/// any is created.
+pub const MAX_MESSAGES: usize = 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum BbsError {
+    /// The signature is not a valid `(A, e)` encoding.
+    InvalidSignature,
+    /// The disclosed indexes are not sorted indexes of the signed messages.
+    InvalidIndexes,
+    /// A scalar to invert is zero, which happens with negligible probability.
+    NonInvertibleScalar,
+    /// More than [`MAX_MESSAGES`] messages.
+    TooManyMessages,
+}
+
+impl fmt::Display for BbsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BbsError::InvalidSignature => write!(f, "invalid BBS signature"),
+            BbsError::InvalidIndexes => write!(f, "disclosed indexes are not sorted indexes of the signed messages"),
+            BbsError::NonInvertibleScalar => write!(f, "non-invertible scalar, retry with other inputs"),
+            BbsError::TooManyMessages => write!(f, "more than {} messages", MAX_MESSAGES),
+        }
+    }
+}
+
+impl std::error::Error for BbsError {}
+
+/// A BBS secret key.
+#[derive(Clone)]
+pub struct SecretKey(Scalar);
+
+/// A BBS public key, a point of G2.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PublicKey(G2Affine);
+
+impl SecretKey {
+    pub fn random() -> Self {
+        loop {
+            let mut key_material = [0u8; 32];
+            OsRng.fill_bytes(&mut key_material);
+            if let Some(key) = Self::generate(&key_material, &[], None) {
+                return key;
+            }
+        }
+    }
+
+    /// Derives a secret key from `key_material` of at least 32 bytes
+    /// (`KeyGen`), `key_dst` defaulting to the ciphersuite's.
+    pub fn generate(key_material: &[u8], key_info: &[u8], key_dst: Option<&[u8]>) -> Option<Self> {
+        if key_material.len() < 32 || key_info.len() > u16::MAX as usize {
+            return None;
+        }
+
+        let key_dst = key_dst.map_or_else(|| dst(b"KEYGEN_DST_"), <[u8]>::to_vec);
+        let derive_input = [key_material, &(key_info.len() as u16).to_be_bytes(), key_info].concat();
+        let scalar = hash_to_scalar(&derive_input, &key_dst);
+        (scalar != Scalar::zero()).then_some(SecretKey(scalar))
+    }
+
+    /// Parses the big-endian encoding of a secret key.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let scalar = scalar_from_octets(bytes)?;
+        (scalar != Scalar::zero()).then_some(SecretKey(scalar))
+    }
+
+    pub fn to_bytes(&self) -> [u8; SCALAR_LEN] {
+        scalar_to_octets(&self.0)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(G2Affine::from(G2Affine::generator() * self.0))
+    }
+
+    /// Signs `messages`, all bound to `header`.
+    pub fn sign(&self, header: &[u8], messages: &[Vec<u8>]) -> Result<Vec<u8>, BbsError> {
+        if messages.len() > MAX_MESSAGES {
+            return Err(BbsError::TooManyMessages);
+        }
+
+        let public_key = self.public_key();
+        let scalars = messages_to_scalars(messages);
+        let generators = Generators::create(messages.len());
+        let domain = calculate_domain(&public_key, &generators, header);
+
+        let mut e_input = scalar_to_octets(&self.0).to_vec();
+        for scalar in &scalars {
+            e_input.extend(scalar_to_octets(scalar));
+        }
+        e_input.extend(scalar_to_octets(&domain));
+        let e = hash_to_scalar(&e_input, &dst(b"H2S_"));
+
+        let b = generators.commitment(&domain, scalars.iter().enumerate());
+        let a = b * invert(&(self.0 + e))?;
+
+        let mut signature = G1Affine::from(a).to_compressed().to_vec();
+        signature.extend(scalar_to_octets(&e));
+        Ok(signature)
+    }
+}
+
+impl PublicKey {
+    /// Parses a compressed G2 point.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; 96] = bytes.try_into().ok()?;
+        let point = Option::<G2Affine>::from(G2Affine::from_compressed(&bytes))?;
+        (!bool::from(point.is_identity())).then_some(PublicKey(point))
+    }
+
+    pub fn to_bytes(self) -> [u8; 96] {
+        self.0.to_compressed()
+    }
+
+    /// Parses an uncompressed G2 point (`x || y`).
+    pub fn from_uncompressed(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; 192] = bytes.try_into().ok()?;
+        let point = Option::<G2Affine>::from(G2Affine::from_uncompressed(&bytes))?;
+        (!bool::from(point.is_identity())).then_some(PublicKey(point))
+    }
+
+    pub fn to_uncompressed(self) -> [u8; 192] {
+        self.0.to_uncompressed()
+    }
+
+    /// Checks a signature over all of `messages`.
+    pub fn verify(&self, header: &[u8], messages: &[Vec<u8>], signature: &[u8]) -> bool {
+        let Some((a, e)) = decode_signature(signature) else {
+            return false;
+        };
+        if messages.len() > MAX_MESSAGES {
+            return false;
+        }
+
+        let scalars = messages_to_scalars(messages);
+        let generators = Generators::create(messages.len());
+        let domain = calculate_domain(self, &generators, header);
+        let b = generators.commitment(&domain, scalars.iter().enumerate());
+
+        // e(A, W + BP2 * e) * e(B, -BP2) == 1
+        let w = G2Affine::from(G2Projective::from(self.0) + G2Affine::generator() * e);
+        pairings_cancel(&a, &w, &G1Affine::from(b))
+    }
+
+    /// Creates a proof of knowledge of `signature` that discloses only the
+    /// messages at `disclosed` (sorted indexes), bound to the presentation
+    /// header `ph`.
+    pub fn prove(
+        &self,
+        signature: &[u8],
+        header: &[u8],
+        ph: &[u8],
+        messages: &[Vec<u8>],
+        disclosed: &[usize],
+    ) -> Result<Vec<u8>, BbsError> {
+        let (a, e) = decode_signature(signature).ok_or(BbsError::InvalidSignature)?;
+        if messages.len() > MAX_MESSAGES {
+            return Err(BbsError::TooManyMessages);
+        }
+        if !is_sorted_subset(disclosed, messages.len()) {
+            return Err(BbsError::InvalidIndexes);
+        }
+
+        let scalars = messages_to_scalars(messages);
+        let generators = Generators::create(messages.len());
+        let domain = calculate_domain(self, &generators, header);
+        let undisclosed: Vec<usize> = (0..messages.len()).filter(|i| !disclosed.contains(i)).collect();
+
+        let r1 = random_scalar();
+        let r2 = random_scalar();
+        let e_tilde = random_scalar();
+        let r1_tilde = random_scalar();
+        let r3_tilde = random_scalar();
+        let m_tilde: Vec<Scalar> = undisclosed.iter().map(|_| random_scalar()).collect();
+
+        let b = generators.commitment(&domain, scalars.iter().enumerate());
+        let d = b * r2;
+        let a_bar = G1Projective::from(a) * (r1 * r2);
+        let b_bar = d * r1 - a_bar * e;
+        let t1 = a_bar * e_tilde + d * r1_tilde;
+        let mut t2 = d * r3_tilde;
+        for (j, m) in undisclosed.iter().zip(&m_tilde) {
+            t2 += generators.h[*j] * m;
+        }
+
+        let points = [a_bar, b_bar, d].map(G1Affine::from);
+        let challenge = challenge(
+            &points,
+            &[t1, t2].map(G1Affine::from),
+            &domain,
+            disclosed.iter().map(|i| (*i, scalars[*i])),
+            ph,
+        );
+
+        let r3 = invert(&r2)?;
+        let mut proof = Vec::new();
+        for point in &points {
+            proof.extend(point.to_compressed());
+        }
+        proof.extend(scalar_to_octets(&(e_tilde + e * challenge)));
+        proof.extend(scalar_to_octets(&(r1_tilde - r1 * challenge)));
+        proof.extend(scalar_to_octets(&(r3_tilde - r3 * challenge)));
+        for (j, m) in undisclosed.iter().zip(&m_tilde) {
+            proof.extend(scalar_to_octets(&(m + scalars[*j] * challenge)));
+        }
+        proof.extend(scalar_to_octets(&challenge));
+
+        Ok(proof)
+    }
+
+    /// Checks a proof created by [`PublicKey::prove`] against the disclosed
+    /// messages, given with their index in the signed list.
+    pub fn verify_proof(&self, proof: &[u8], header: &[u8], ph: &[u8], disclosed: &[(usize, Vec<u8>)]) -> bool {
+        let fixed = 3 * POINT_LEN + 4 * SCALAR_LEN;
+        if proof.len() < fixed || !(proof.len() - fixed).is_multiple_of(SCALAR_LEN) {
+            return false;
+        }
+
+        // The proof claims how many messages were signed: bound it before
+        // creating their generators
+        let undisclosed_count = (proof.len() - fixed) / SCALAR_LEN;
+        let total = disclosed.len() + undisclosed_count;
+        if total > MAX_MESSAGES {
+            return false;
+        }
+        let indexes: Vec<usize> = disclosed.iter().map(|(i, _)| *i).collect();
+        if !is_sorted_subset(&indexes, total) {
+            return false;
+        }
+
+        let mut points = Vec::new();
+        for chunk in proof[..3 * POINT_LEN].chunks(POINT_LEN) {
+            let bytes: [u8; POINT_LEN] = chunk.try_into().unwrap();
+            match Option::<G1Affine>::from(G1Affine::from_compressed(&bytes)) {
+                Some(point) if !bool::from(point.is_identity()) => points.push(point),
+                _ => return false,
+            }
+        }
+
+        let scalars: Option<Vec<Scalar>> = proof[3 * POINT_LEN..]
+            .chunks(SCALAR_LEN)
+            .map(scalar_from_octets)
+            .collect();
+        let Some(scalars) = scalars else {
+            return false;
+        };
+
+        let (a_bar, b_bar, d) = (points[0], points[1], points[2]);
+        let (e_hat, r1_hat, r3_hat) = (scalars[0], scalars[1], scalars[2]);
+        let m_hat = &scalars[3..3 + undisclosed_count];
+        let cp = scalars[scalars.len() - 1];
+
+        let disclosed_scalars: Vec<(usize, Scalar)> = disclosed.iter()
+            .map(|(i, message)| (*i, hash_to_scalar(message, &dst(b"MAP_MSG_TO_SCALAR_AS_HASH_"))))
+            .collect();
+
+        let generators = Generators::create(total);
+        let domain = calculate_domain(self, &generators, header);
+
+        let t1 = b_bar * cp + a_bar * e_hat + d * r1_hat;
+        let bv = generators.commitment(&domain, disclosed_scalars.iter().map(|(i, m)| (*i, m)));
+        let mut t2 = bv * cp + d * r3_hat;
+        let undisclosed = (0..total).filter(|i| !indexes.contains(i));
+        for (j, m) in undisclosed.zip(m_hat) {
+            t2 += generators.h[j] * m;
+        }
+
+        let expected = challenge(
+            &[a_bar, b_bar, d],
+            &[t1, t2].map(G1Affine::from),
+            &domain,
+            disclosed_scalars.into_iter(),
+            ph,
+        );
+
+        // e(Abar, W) * e(Bbar, -BP2) == 1
+        expected == cp && pairings_cancel(&a_bar, &self.0, &b_bar)
+    }
+}
+
+/// `P1` and the message generators `Q_1, H_1, …, H_L` of the ciphersuite.
+pub struct Generators {
+    p1: G1Projective,
+    q1: G1Projective,
+    h: Vec<G1Projective>,
+}
+
+impl Generators {
+    /// Generators for `count` messages.
+    pub fn create(count: usize) -> Self {
+        let p1 = create_generators(&[API_ID, b"BP_MESSAGE_GENERATOR_SEED"].concat(), 1)[0];
+        let mut generators = create_generators(&[API_ID, b"MESSAGE_GENERATOR_SEED"].concat(), count + 1);
+        let h = generators.split_off(1);
+
+        Generators { p1, q1: generators[0], h }
+    }
+
+    /// Compressed `P1` and `Q_1, H_1, …, H_L`.
+    pub fn compressed(&self) -> ([u8; POINT_LEN], Vec<[u8; POINT_LEN]>) {
+        let messages = std::iter::once(&self.q1)
+            .chain(&self.h)
+            .map(|point| G1Affine::from(point).to_compressed())
+            .collect();
+
+        (G1Affine::from(self.p1).to_compressed(), messages)
+    }
+
+    /// `P1 + Q_1 * domain + Σ H_i * msg_i` over the given messages.
+    fn commitment<'a>(&self, domain: &Scalar, messages: impl Iterator<Item = (usize, &'a Scalar)>) -> G1Projective {
+        let mut b = self.p1 + self.q1 * domain;
+        for (i, m) in messages {
+            b += self.h[i] * m;
+        }
+        b
+    }
+}
+
+fn create_generators(seed: &[u8], count: usize) -> Vec<G1Projective> {
+    let seed_dst = dst(b"SIG_GENERATOR_SEED_");
+    let generator_dst = dst(b"SIG_GENERATOR_DST_");
+
+    let mut v = expand_message_xmd(seed, &seed_dst, EXPAND_LEN);
+    (1..=count as u64)
+        .map(|i| {
+            v = expand_message_xmd(&[v.as_slice(), &i.to_be_bytes()].concat(), &seed_dst, EXPAND_LEN);
+            <G1Projective as HashToCurve<ExpandMsgXmdSha256>>::hash_to_curve(&v, &generator_dst)
+        })
+        .collect()
+}
+
+fn calculate_domain(public_key: &PublicKey, generators: &Generators, header: &[u8]) -> Scalar {
+    let mut input = public_key.to_bytes().to_vec();
+    input.extend((generators.h.len() as u64).to_be_bytes());
+    for point in generators.compressed().1 {
+        input.extend(point);
+    }
+    input.extend(API_ID);
+    input.extend((header.len() as u64).to_be_bytes());
+    input.extend(header);
+
+    hash_to_scalar(&input, &dst(b"H2S_"))
+}
+
+fn challenge(
+    points: &[G1Affine; 3],
+    commitments: &[G1Affine; 2],
+    domain: &Scalar,
+    disclosed: impl ExactSizeIterator<Item = (usize, Scalar)>,
+    ph: &[u8],
+) -> Scalar {
+    let mut input = (disclosed.len() as u64).to_be_bytes().to_vec();
+    for (i, m) in disclosed {
+        input.extend((i as u64).to_be_bytes());
+        input.extend(scalar_to_octets(&m));
+    }
+    for point in points.iter().chain(commitments) {
+        input.extend(point.to_compressed());
+    }
+    input.extend(scalar_to_octets(domain));
+    input.extend((ph.len() as u64).to_be_bytes());
+    input.extend(ph);
+
+    hash_to_scalar(&input, &dst(b"H2S_"))
+}
+
+fn pairings_cancel(a: &G1Affine, w: &G2Affine, b: &G1Affine) -> bool {
+    let w = G2Prepared::from(*w);
+    let minus_bp2 = G2Prepared::from(-G2Affine::generator());
+
+    multi_miller_loop(&[(a, &w), (b, &minus_bp2)]).final_exponentiation() == Gt::identity()
+}
+
+fn decode_signature(signature: &[u8]) -> Option<(G1Affine, Scalar)> {
+    if signature.len() != POINT_LEN + SCALAR_LEN {
+        return None;
+    }
+
+    let point: [u8; POINT_LEN] = signature[..POINT_LEN].try_into().ok()?;
+    let a = Option::<G1Affine>::from(G1Affine::from_compressed(&point))?;
+    let e = scalar_from_octets(&signature[POINT_LEN..])?;
+
+    (!bool::from(a.is_identity()) && e != Scalar::zero()).then_some((a, e))
+}
+
+fn messages_to_scalars(messages: &[Vec<u8>]) -> Vec<Scalar> {
+    let map_dst = dst(b"MAP_MSG_TO_SCALAR_AS_HASH_");
+    messages.iter().map(|m| hash_to_scalar(m, &map_dst)).collect()
+}
+
+fn is_sorted_subset(indexes: &[usize], len: usize) -> bool {
+    indexes.windows(2).all(|w| w[0] < w[1]) && indexes.last().is_none_or(|i| *i < len)
+}
+
+fn dst(suffix: &[u8]) -> Vec<u8> {
+    [API_ID, suffix].concat()
+}
+
+fn hash_to_scalar(message: &[u8], dst: &[u8]) -> Scalar {
+    let uniform = expand_message_xmd(message, dst, EXPAND_LEN);
+
+    // OS2IP(uniform) mod r, from the little-endian wide representation
+    let mut wide = [0u8; 64];
+    for (i, byte) in uniform.iter().rev().enumerate() {
+        wide[i] = *byte;
+    }
+    Scalar::from_bytes_wide(&wide)
+}
+
+fn random_scalar() -> Scalar {
+    let mut wide = [0u8; 64];
+    OsRng.fill_bytes(&mut wide);
+    Scalar::from_bytes_wide(&wide)
+}
+
+fn invert(scalar: &Scalar) -> Result<Scalar, BbsError> {
+    Option::from(scalar.invert()).ok_or(BbsError::NonInvertibleScalar)
+}
+
+fn scalar_to_octets(scalar: &Scalar) -> [u8; SCALAR_LEN] {
+    let mut bytes = scalar.to_bytes();
+    bytes.reverse();
+    bytes
+}
+
+fn scalar_from_octets(bytes: &[u8]) -> Option<Scalar> {
+    let mut bytes: [u8; SCALAR_LEN] = bytes.try_into().ok()?;
+    bytes.reverse();
+    Option::from(Scalar::from_bytes(&bytes))
+}
+
+/// `expand_message_xmd` with SHA-256 (RFC 9380, section 5.3.1).
+fn expand_message_xmd(message: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let mut dst_prime = dst.to_vec();
+    dst_prime.push(dst.len() as u8);
+
+    let b0 = Sha256::new()
+        .chain_update([0u8; 64])
+        .chain_update(message)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+
+    let mut bi = Sha256::new().chain_update(b0).chain_update([1u8]).chain_update(&dst_prime).finalize();
+    let mut uniform = bi.to_vec();
+
+    for i in 2..=len.div_ceil(32) {
+        let xored: Vec<u8> = b0.iter().zip(bi.iter()).map(|(x, y)| x ^ y).collect();
+        bi = Sha256::new().chain_update(xored).chain_update([i as u8]).chain_update(&dst_prime).finalize();
+        uniform.extend_from_slice(&bi);
+    }
+
+    uniform.truncate(len);
+    uniform
+}
+
+/// Adapter feeding [`expand_message_xmd`] to the hash-to-curve of `bls12_381`.
+struct ExpandMsgXmdSha256;
+
+struct Expanded {
+    bytes: Vec<u8>,
+    offset: usize,
+}
+
+impl<'x> InitExpandMessage<'x> for ExpandMsgXmdSha256 {
+    type Expander = Expanded;
+
+    fn init_expand(message: &[u8], dst: &'x [u8], len_in_bytes: usize) -> Expanded {
+        Expanded { bytes: expand_message_xmd(message, dst, len_in_bytes), offset: 0 }
+    }
+}
+
+impl<'x> ExpandMessageState<'x> for Expanded {
+    fn read_into(&mut self, output: &mut [u8]) -> usize {
+        let len = output.len().min(self.remain());
+        output[..len].copy_from_slice(&self.bytes[self.offset..self.offset + len]);
+        self.offset += len;
+        len
+    }
+
+    fn remain(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+}
@@ -0,0 +1,423 @@
+//! The `bbs-2023` cryptosuite: a base proof signs every N-Quads statement of
+//! an attribute as a separate BBS message, and derived proofs disclose only
+//! the statements selected by JSON pointers.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use ciborium::Value as Cbor;
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use crate::bbs;
+use crate::jsonld::context::ContextLoader;
+use crate::jsonld::{self, rdfc, select, JsonLdError};
+use crate::keystore::ServiceKey;
+use crate::proof;
+use crate::resolver::PublicKey;
+
+/// CBOR tag bytes opening a base proof.
+const BASE_PROOF_HEADER: [u8; 3] = [0xd9, 0x5d, 0x02];
+
+/// CBOR tag bytes opening a derived proof.
+const DERIVED_PROOF_HEADER: [u8; 3] = [0xd9, 0x5d, 0x03];
+
+#[derive(Debug)]
+pub enum Bbs2023Error {
+    JsonLd(JsonLdError),
+    /// The key or the proof value cannot be used with the suite.
+    InvalidProof(String),
+    /// The JSON pointers do not select whole statements of the signed document.
+    Selection(String),
+}
+
+impl fmt::Display for Bbs2023Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bbs2023Error::JsonLd(e) => write!(f, "{}", e),
+            Bbs2023Error::InvalidProof(msg) => write!(f, "invalid bbs-2023 proof: {}", msg),
+            Bbs2023Error::Selection(msg) => write!(f, "invalid selection: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Bbs2023Error {}
+
+impl From<JsonLdError> for Bbs2023Error {
+    fn from(e: JsonLdError) -> Self {
+        Bbs2023Error::JsonLd(e)
+    }
+}
+
+/// Pointers disclosed by every derived proof of the attribute at `path`: the
+/// id and type of the entity and the type of the attribute.
+pub fn mandatory_pointers(path: &[String]) -> Vec<String> {
+    vec!["/id".into(), "/type".into(), format!("{}/type", proof::attribute_pointer(path))]
+}
+
+/// Whether `proof_value` is a base proof, which only the holder should see.
+pub fn is_base_proof(proof_value: &str) -> bool {
+    decode_multibase(proof_value).is_ok_and(|bytes| bytes.starts_with(&BASE_PROOF_HEADER))
+}
+
+/// Creates the base proof of `document` with the BLS12-381 key `key`.
+///
+/// `document` and `options` must already carry the `@context` of the proof.
+pub fn sign(
+    document: &Value,
+    options: &Value,
+    key: &ServiceKey,
+    mandatory_pointers: &[String],
+    loader: &ContextLoader,
+) -> Result<String, Bbs2023Error> {
+    let PublicKey::Bls12381G2(public_key) = key.public_key() else {
+        return Err(Bbs2023Error::InvalidProof("a BLS12-381 G2 key is required".into()));
+    };
+
+    let mut hmac_key = [0u8; 32];
+    OsRng.fill_bytes(&mut hmac_key);
+
+    let statements = Statements::new(document, &hmac_key, loader)?;
+    let mandatory = statements.select(mandatory_pointers, loader)?;
+    let (mandatory_nquads, messages) = statements.split(&mandatory);
+    if messages.len() > bbs::MAX_MESSAGES {
+        return Err(Bbs2023Error::JsonLd(JsonLdError::TooComplex));
+    }
+    let header = bbs_header(options, &mandatory_nquads, loader)?;
+
+    let signature = key.sign_messages(&header, &messages)
+        .ok_or_else(|| Bbs2023Error::InvalidProof("a BLS12-381 G2 key is required".into()))?
+        .map_err(|e| Bbs2023Error::InvalidProof(e.to_string()))?;
+
+    let components = Cbor::Array(vec![
+        Cbor::Bytes(signature),
+        Cbor::Bytes(header),
+        Cbor::Bytes(public_key.to_bytes().to_vec()),
+        Cbor::Bytes(hmac_key.to_vec()),
+        Cbor::Array(mandatory_pointers.iter().map(|p| Cbor::Text(p.clone())).collect()),
+    ]);
+
+    Ok(encode_multibase(&BASE_PROOF_HEADER, &components))
+}
+
+/// Derives, from a base proof of `document`, a proof disclosing only the
+/// values named by `selective_pointers` (and the mandatory ones).
+///
+/// `document` must already carry the `@context` of the proof. Returns the
+/// revealed document and the `proofValue` of the derived proof.
+pub fn derive(
+    document: &Value,
+    proof_value: &str,
+    selective_pointers: &[String],
+    presentation_header: &[u8],
+    loader: &ContextLoader,
+) -> Result<(Value, String), Bbs2023Error> {
+    let base = BaseProof::decode(proof_value)?;
+    let public_key = bbs::PublicKey::from_bytes(&base.public_key)
+        .ok_or_else(|| Bbs2023Error::InvalidProof("invalid public key".into()))?;
+
+    let statements = Statements::new(document, &base.hmac_key, loader)?;
+    let mandatory = statements.select(&base.mandatory_pointers, loader)?;
+
+    let mut combined_pointers = base.mandatory_pointers.clone();
+    combined_pointers.extend(selective_pointers.iter().cloned());
+    let combined = statements.select(&combined_pointers, loader)?;
+
+    // Revealed statements are either mandatory or disclosed BBS messages
+    let mandatory_indexes: Vec<usize> = combined.iter()
+        .enumerate()
+        .filter(|(_, i)| mandatory.contains(i))
+        .map(|(position, _)| position)
+        .collect();
+
+    let non_mandatory: Vec<usize> = (0..statements.nquads.len()).filter(|i| !mandatory.contains(i)).collect();
+    let selective_indexes: Vec<usize> = non_mandatory.iter()
+        .enumerate()
+        .filter(|(_, i)| combined.contains(i))
+        .map(|(position, _)| position)
+        .collect();
+
+    let (_, messages) = statements.split(&mandatory);
+    let proof = public_key.prove(&base.signature, &base.header, presentation_header, &messages, &selective_indexes)
+        .map_err(|e| Bbs2023Error::InvalidProof(e.to_string()))?;
+
+    // The verifier labels the blank nodes of the revealed document with
+    // RDFC-1.0; tell it which HMAC label each canonical label stands for
+    let reveal = select::select(&statements.skolemized, &combined_pointers)?;
+    let reveal_quads = select::deskolemized_quads(&reveal, loader)?;
+    let mut label_map = Vec::new();
+    for (input, canonical) in rdfc::canonical_labels(&reveal_quads)? {
+        let (Some(from), Some(to)) = (
+            label_index(&canonical, "c14n"),
+            statements.labels.get(&input).and_then(|label| label_index(label, "b")),
+        ) else {
+            return Err(Bbs2023Error::Selection(format!("blank node '{}' is not part of the signed document", input)));
+        };
+        label_map.push((from, to));
+    }
+    label_map.sort();
+
+    let components = Cbor::Array(vec![
+        Cbor::Bytes(proof),
+        Cbor::Map(label_map.into_iter().map(|(from, to)| (Cbor::Integer(from.into()), Cbor::Integer(to.into()))).collect()),
+        Cbor::Array(mandatory_indexes.into_iter().map(|i| Cbor::Integer((i as u64).into())).collect()),
+        Cbor::Array(selective_indexes.into_iter().map(|i| Cbor::Integer((i as u64).into())).collect()),
+        Cbor::Bytes(presentation_header.to_vec()),
+    ]);
+
+    Ok((select::unskolemize(&reveal), encode_multibase(&DERIVED_PROOF_HEADER, &components)))
+}
+
+/// Checks a base or derived proof of `document`.
+///
+/// `document` and `options` must already carry the `@context` of the proof.
+pub fn verify(
+    document: &Value,
+    options: &Value,
+    proof_value: &str,
+    public_key: &bbs::PublicKey,
+    loader: &ContextLoader,
+) -> Result<bool, Bbs2023Error> {
+    if is_base_proof(proof_value) {
+        verify_base(document, options, proof_value, public_key, loader)
+    } else {
+        verify_derived(document, options, proof_value, public_key, loader)
+    }
+}
+
+fn verify_base(
+    document: &Value,
+    options: &Value,
+    proof_value: &str,
+    public_key: &bbs::PublicKey,
+    loader: &ContextLoader,
+) -> Result<bool, Bbs2023Error> {
+    let base = BaseProof::decode(proof_value)?;
+    if base.public_key != public_key.to_bytes() {
+        return Ok(false);
+    }
+
+    let statements = Statements::new(document, &base.hmac_key, loader)?;
+    let mandatory = statements.select(&base.mandatory_pointers, loader)?;
+    let (mandatory_nquads, messages) = statements.split(&mandatory);
+    let header = bbs_header(options, &mandatory_nquads, loader)?;
+
+    Ok(header == base.header && public_key.verify(&header, &messages, &base.signature))
+}
+
+fn verify_derived(
+    document: &Value,
+    options: &Value,
+    proof_value: &str,
+    public_key: &bbs::PublicKey,
+    loader: &ContextLoader,
+) -> Result<bool, Bbs2023Error> {
+    let derived = DerivedProof::decode(proof_value)?;
+
+    let expanded = jsonld::expand::expand(document, loader)?;
+    let quads = jsonld::rdf::to_rdf(&expanded)?;
+
+    let mut labels = HashMap::new();
+    for (input, canonical) in rdfc::canonical_labels(&quads)? {
+        match label_index(&canonical, "c14n").and_then(|i| derived.label_map.get(&i)) {
+            Some(label) => labels.insert(input, format!("b{}", label)),
+            None => return Ok(false),
+        };
+    }
+    let nquads = rdfc::relabel(&quads, &labels);
+
+    if derived.mandatory_indexes.iter().any(|i| *i >= nquads.len()) {
+        return Ok(false);
+    }
+
+    let mut mandatory = Vec::new();
+    let mut disclosed = Vec::new();
+    for (i, nquad) in nquads.into_iter().enumerate() {
+        if derived.mandatory_indexes.contains(&i) {
+            mandatory.push(nquad);
+        } else {
+            disclosed.push(nquad.into_bytes());
+        }
+    }
+
+    if disclosed.len() != derived.selective_indexes.len() {
+        return Ok(false);
+    }
+
+    let header = bbs_header(options, &mandatory, loader)?;
+    let disclosed: Vec<(usize, Vec<u8>)> = derived.selective_indexes.iter().copied().zip(disclosed).collect();
+
+    Ok(public_key.verify_proof(&derived.proof, &header, &derived.presentation_header, &disclosed))
+}
+
+/// The canonical statements of a document, with HMAC-derived blank node labels.
+struct Statements {
+    skolemized: Value,
+    /// Input blank node label → `bN` label.
+    labels: HashMap<String, String>,
+    /// Sorted N-Quads lines.
+    nquads: Vec<String>,
+}
+
+impl Statements {
+    fn new(document: &Value, hmac_key: &[u8], loader: &ContextLoader) -> Result<Self, Bbs2023Error> {
+        let skolemized = select::skolemize(document, loader)?;
+        let quads = select::deskolemized_quads(&skolemized, loader)?;
+        let labels = shuffled_labels(rdfc::canonical_labels(&quads)?, hmac_key);
+        let nquads = rdfc::relabel(&quads, &labels);
+
+        Ok(Statements { skolemized, labels, nquads })
+    }
+
+    /// Indexes of the statements made by the values named by `pointers`.
+    fn select(&self, pointers: &[String], loader: &ContextLoader) -> Result<BTreeSet<usize>, Bbs2023Error> {
+        let selection = select::select(&self.skolemized, pointers)?;
+        let quads = select::deskolemized_quads(&selection, loader)?;
+
+        rdfc::relabel(&quads, &self.labels)
+            .iter()
+            .map(|nquad| self.nquads.binary_search(nquad).map_err(|_| {
+                Bbs2023Error::Selection(format!("statement '{}' is not part of the signed document", nquad.trim_end()))
+            }))
+            .collect()
+    }
+
+    /// Splits the statements into the mandatory ones and the BBS messages.
+    fn split(&self, mandatory: &BTreeSet<usize>) -> (Vec<String>, Vec<Vec<u8>>) {
+        let mut mandatory_nquads = Vec::new();
+        let mut messages = Vec::new();
+
+        for (i, nquad) in self.nquads.iter().enumerate() {
+            if mandatory.contains(&i) {
+                mandatory_nquads.push(nquad.clone());
+            } else {
+                messages.push(nquad.clone().into_bytes());
+            }
+        }
+
+        (mandatory_nquads, messages)
+    }
+}
+
+/// Replaces the canonical labels by `bN`, numbered in the order of their HMAC,
+/// so that labels do not leak the shape of undisclosed statements.
+fn shuffled_labels(canonical: HashMap<String, String>, hmac_key: &[u8]) -> HashMap<String, String> {
+    let mut digests: Vec<(String, String)> = canonical.into_iter()
+        .map(|(input, label)| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(hmac_key).expect("HMAC accepts keys of any size");
+            mac.update(label.as_bytes());
+            (URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()), input)
+        })
+        .collect();
+    digests.sort();
+
+    digests.into_iter()
+        .enumerate()
+        .map(|(i, (_, input))| (input, format!("b{}", i)))
+        .collect()
+}
+
+/// `SHA-256(canonical proof options) || SHA-256(mandatory statements)`.
+fn bbs_header(options: &Value, mandatory: &[String], loader: &ContextLoader) -> Result<Vec<u8>, Bbs2023Error> {
+    let mut header = Sha256::digest(jsonld::canonize(options, loader)?.as_bytes()).to_vec();
+    header.extend(Sha256::digest(mandatory.concat().as_bytes()));
+    Ok(header)
+}
+
+fn label_index(label: &str, prefix: &str) -> Option<u64> {
+    label.strip_prefix(prefix)?.parse().ok()
+}
+
+struct BaseProof {
+    signature: Vec<u8>,
+    header: Vec<u8>,
+    public_key: Vec<u8>,
+    hmac_key: Vec<u8>,
+    mandatory_pointers: Vec<String>,
+}
+
+impl BaseProof {
+    fn decode(proof_value: &str) -> Result<Self, Bbs2023Error> {
+        let invalid = || Bbs2023Error::InvalidProof("malformed base proof".into());
+        let components = decode_components(proof_value, &BASE_PROOF_HEADER)?;
+
+        let [signature, header, public_key, hmac_key, Cbor::Array(pointers)] = <[Cbor; 5]>::try_from(components)
+            .map_err(|_| invalid())?
+        else {
+            return Err(invalid());
+        };
+
+        Ok(BaseProof {
+            signature: signature.into_bytes().map_err(|_| invalid())?,
+            header: header.into_bytes().map_err(|_| invalid())?,
+            public_key: public_key.into_bytes().map_err(|_| invalid())?,
+            hmac_key: hmac_key.into_bytes().map_err(|_| invalid())?,
+            mandatory_pointers: pointers.into_iter()
+                .map(|p| p.into_text().map_err(|_| invalid()))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+struct DerivedProof {
+    proof: Vec<u8>,
+    /// Canonical label index → HMAC label index.
+    label_map: HashMap<u64, u64>,
+    mandatory_indexes: Vec<usize>,
+    selective_indexes: Vec<usize>,
+    presentation_header: Vec<u8>,
+}
+
+impl DerivedProof {
+    fn decode(proof_value: &str) -> Result<Self, Bbs2023Error> {
+        let invalid = || Bbs2023Error::InvalidProof("malformed derived proof".into());
+        let components = decode_components(proof_value, &DERIVED_PROOF_HEADER)?;
+
+        let [proof, Cbor::Map(label_map), Cbor::Array(mandatory), Cbor::Array(selective), presentation_header] =
+            <[Cbor; 5]>::try_from(components).map_err(|_| invalid())?
+        else {
+            return Err(invalid());
+        };
+
+        let integer = |value: &Cbor| value.as_integer().and_then(|i| u64::try_from(i).ok()).ok_or_else(invalid);
+        let indexes = |values: Vec<Cbor>| -> Result<Vec<usize>, Bbs2023Error> {
+            values.iter().map(|v| integer(v).map(|i| i as usize)).collect()
+        };
+
+        Ok(DerivedProof {
+            proof: proof.into_bytes().map_err(|_| invalid())?,
+            label_map: label_map.iter()
+                .map(|(from, to)| Ok((integer(from)?, integer(to)?)))
+                .collect::<Result<_, Bbs2023Error>>()?,
+            mandatory_indexes: indexes(mandatory)?,
+            selective_indexes: indexes(selective)?,
+            presentation_header: presentation_header.into_bytes().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Encodes proof components as multibase base64url (`u…`) tagged CBOR.
+fn encode_multibase(header: &[u8], components: &Cbor) -> String {
+    let mut bytes = header.to_vec();
+    ciborium::into_writer(components, &mut bytes).expect("CBOR encoding into memory cannot fail");
+    format!("u{}", URL_SAFE_NO_PAD.encode(bytes))
+}
+
+fn decode_multibase(proof_value: &str) -> Result<Vec<u8>, Bbs2023Error> {
+    proof_value.strip_prefix('u')
+        .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+        .ok_or_else(|| Bbs2023Error::InvalidProof("proofValue must be multibase base64url".into()))
+}
+
+fn decode_components(proof_value: &str, header: &[u8]) -> Result<Vec<Cbor>, Bbs2023Error> {
+    let bytes = decode_multibase(proof_value)?;
+    let body = bytes.strip_prefix(header)
+        .ok_or_else(|| Bbs2023Error::InvalidProof("unexpected proof header".into()))?;
+
+    match ciborium::from_reader(body) {
+        Ok(Cbor::Array(components)) => Ok(components),
+        _ => Err(Bbs2023Error::InvalidProof("proof components are not a CBOR array".into())),
+    }
+}
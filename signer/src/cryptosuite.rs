@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use crate::jcs;
+use crate::keystore::KeyAlgorithm;
+use crate::jsonld::{self, JsonLdError};
+use crate::jsonld::context::{ContextLoader, DATA_INTEGRITY_CONTEXT, NGSI_LD_CORE_CONTEXT};
+
+/// Data Integrity cryptosuites supported for attribute proofs.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Cryptosuite {
+    /// Ed25519 over RDFC-1.0 canonical N-Quads.
+    #[default]
+    #[serde(rename = "eddsa-rdfc-2022")]
+    EddsaRdfc2022,
+    /// Ed25519 over the RFC 8785 (JCS) serialization, without JSON-LD processing.
+    #[serde(rename = "eddsa-jcs-2022")]
+    EddsaJcs2022,
+    /// ECDSA (P-256 or P-384) over RDFC-1.0 canonical N-Quads.
+    #[serde(rename = "ecdsa-rdfc-2019")]
+    EcdsaRdfc2019,
+    /// ECDSA (P-256 or P-384) over the RFC 8785 (JCS) serialization.
+    #[serde(rename = "ecdsa-jcs-2019")]
+    EcdsaJcs2019,
+    /// BBS (BLS12-381) over RDFC-1.0 statements, with selective disclosure.
+    #[serde(rename = "bbs-2023")]
+    Bbs2023,
+    /// ML-DSA-65 (FIPS 204) over RDFC-1.0 canonical N-Quads, resistant to
+    /// quantum attacks. Private to this service: no specification defines it.
+    #[serde(rename = "mldsa65-rdfc-2024")]
+    MlDsa65Rdfc2024,
+}
+
+impl Cryptosuite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cryptosuite::EddsaRdfc2022 => "eddsa-rdfc-2022",
+            Cryptosuite::EddsaJcs2022 => "eddsa-jcs-2022",
+            Cryptosuite::EcdsaRdfc2019 => "ecdsa-rdfc-2019",
+            Cryptosuite::EcdsaJcs2019 => "ecdsa-jcs-2019",
+            Cryptosuite::Bbs2023 => "bbs-2023",
+            Cryptosuite::MlDsa65Rdfc2024 => "mldsa65-rdfc-2024",
+        }
+    }
+
+    /// Parses the `cryptosuite` value of a proof.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "eddsa-rdfc-2022" => Some(Cryptosuite::EddsaRdfc2022),
+            "eddsa-jcs-2022" => Some(Cryptosuite::EddsaJcs2022),
+            "ecdsa-rdfc-2019" => Some(Cryptosuite::EcdsaRdfc2019),
+            "ecdsa-jcs-2019" => Some(Cryptosuite::EcdsaJcs2019),
+            "bbs-2023" => Some(Cryptosuite::Bbs2023),
+            "mldsa65-rdfc-2024" => Some(Cryptosuite::MlDsa65Rdfc2024),
+            _ => None,
+        }
+    }
+
+    /// Key algorithm used when the configuration does not name one.
+    pub fn default_algorithm(&self) -> KeyAlgorithm {
+        match self {
+            Cryptosuite::EddsaRdfc2022 | Cryptosuite::EddsaJcs2022 => KeyAlgorithm::Ed25519,
+            Cryptosuite::EcdsaRdfc2019 | Cryptosuite::EcdsaJcs2019 => KeyAlgorithm::P256,
+            Cryptosuite::Bbs2023 => KeyAlgorithm::Bls12381G2,
+            Cryptosuite::MlDsa65Rdfc2024 => KeyAlgorithm::MlDsa65,
+        }
+    }
+
+    /// Whether proofs of this suite can be made with keys of `algorithm`.
+    pub fn supports(&self, algorithm: KeyAlgorithm) -> bool {
+        match self {
+            Cryptosuite::EddsaRdfc2022 | Cryptosuite::EddsaJcs2022 => algorithm == KeyAlgorithm::Ed25519,
+            Cryptosuite::EcdsaRdfc2019 | Cryptosuite::EcdsaJcs2019 => {
+                matches!(algorithm, KeyAlgorithm::P256 | KeyAlgorithm::P384)
+            }
+            Cryptosuite::Bbs2023 => algorithm == KeyAlgorithm::Bls12381G2,
+            Cryptosuite::MlDsa65Rdfc2024 => algorithm == KeyAlgorithm::MlDsa65,
+        }
+    }
+
+    /// Builds the `@context` of a proof from the context of the entity.
+    ///
+    /// RDF-based suites need the NGSI-LD core context and the Data Integrity
+    /// context to interpret the attribute and the proof options; JCS suites
+    /// simply carry over the context of the entity, if any.
+    pub fn proof_context(&self, entity_context: Option<&Value>) -> Option<Value> {
+        let entity_context = entity_context.filter(|c| !c.is_null());
+
+        match self {
+            Cryptosuite::EddsaJcs2022 | Cryptosuite::EcdsaJcs2019 => entity_context.cloned(),
+            Cryptosuite::EddsaRdfc2022
+            | Cryptosuite::EcdsaRdfc2019
+            | Cryptosuite::Bbs2023
+            | Cryptosuite::MlDsa65Rdfc2024 => {
+                let mut contexts = match entity_context {
+                    Some(Value::Array(items)) => items.clone(),
+                    Some(other) => vec![other.clone()],
+                    None => Vec::new(),
+                };
+
+                let has_core = contexts.iter()
+                    .filter_map(Value::as_str)
+                    .any(|url| url.contains("ngsi-ld-core-context"));
+                if !has_core {
+                    contexts.push(Value::String(NGSI_LD_CORE_CONTEXT.into()));
+                }
+
+                contexts.push(Value::String(DATA_INTEGRITY_CONTEXT.into()));
+                Some(Value::Array(contexts))
+            }
+        }
+    }
+
+    /// Serializes a document in the canonical form hashed by the suite.
+    pub fn canonicalize(&self, document: &Value, loader: &ContextLoader) -> Result<String, JsonLdError> {
+        match self {
+            Cryptosuite::EddsaRdfc2022
+            | Cryptosuite::EcdsaRdfc2019
+            | Cryptosuite::Bbs2023
+            | Cryptosuite::MlDsa65Rdfc2024 => jsonld::canonize(document, loader),
+            Cryptosuite::EddsaJcs2022 | Cryptosuite::EcdsaJcs2019 => jcs::canonicalize(document)
+                .map_err(|e| JsonLdError::InvalidDocument(e.to_string())),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use axum::{Json, extract::{Path, State}, http::{header, HeaderMap, StatusCode}, response::{IntoResponse, Response}};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use tracing::{info, error};
+use crate::handlers::keys::{published_key, PublishedKey, VerificationMethodType};
+use crate::keystore::{KeyAlgorithm, KeyStoreError};
+use crate::state::AppState;
+
+#[derive(Deserialize, ToSchema, Default)]
+pub struct CreateKeyRequest {
+    /// Promote the new key to active right away, retiring the current one
+    #[serde(default)]
+    pub activate: bool,
+    /// `Ed25519` (default), `P-256`, `P-384`, `Bls12381G2` or `ML-DSA-65`
+    #[serde(default)]
+    pub algorithm: KeyAlgorithm,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/keys",
+    request_body = CreateKeyRequest,
+    responses(
+        (status = 201, description = "Key created", body = PublishedKey),
+        (status = 401, description = "Missing or wrong admin token"),
+        (status = 403, description = "Admin endpoints disabled")
+    )
+)]
+pub async fn create_key_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateKeyRequest>,
+) -> Result<(StatusCode, Json<PublishedKey>), Response> {
+    info!("Calling create_key_handler method to manage /admin/keys endpoint");
+
+    if let Some(rejection) = reject_unauthorized(&state, &headers) {
+        return Err(rejection);
+    }
+
+    let mut key_store = state.key_store.write().unwrap();
+
+    let id = key_store.create_key(request.algorithm).map_err(error_response)?.id.clone();
+    if request.activate {
+        key_store.activate(&id).map_err(error_response)?;
+    }
+
+    let key = key_store.keys().iter().find(|k| k.id == id).unwrap();
+    let published = published_key(&state, &key_store, key, VerificationMethodType::Multikey);
+
+    Ok((StatusCode::CREATED, Json(published)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/keys/{id}/activate",
+    params(("id" = String, Path, description = "Key identifier, e.g. key-2")),
+    responses(
+        (status = 200, description = "Key activated", body = PublishedKey),
+        (status = 401, description = "Missing or wrong admin token"),
+        (status = 403, description = "Admin endpoints disabled"),
+        (status = 404, description = "Unknown key"),
+        (status = 409, description = "Key cannot be activated")
+    )
+)]
+pub async fn activate_key_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<PublishedKey>, Response> {
+    info!("Calling activate_key_handler method to manage /admin/keys/{}/activate endpoint", id);
+
+    if let Some(rejection) = reject_unauthorized(&state, &headers) {
+        return Err(rejection);
+    }
+
+    let mut key_store = state.key_store.write().unwrap();
+
+    key_store.activate(&id).map_err(error_response)?;
+
+    let key = key_store.keys().iter().find(|k| k.id == id).unwrap();
+    let published = published_key(&state, &key_store, key, VerificationMethodType::Multikey);
+
+    Ok(Json(published))
+}
+
+/// The response rejecting a request that does not bear the admin token of
+/// the service, `None` to let it through.
+fn reject_unauthorized(state: &AppState, headers: &HeaderMap) -> Option<Response> {
+    let Some(expected) = &state.admin_token else {
+        error!("Key administration over HTTP is disabled");
+
+        let msg = "Key administration over HTTP is disabled: set ADMIN_TOKEN or use the `keys` subcommand";
+        return Some((StatusCode::FORBIDDEN, Json(serde_json::json!({ "error": msg }))).into_response());
+    };
+
+    let token = headers.get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    // Digests are compared so that the time taken does not reveal the token
+    match token {
+        Some(token) if Sha256::digest(token) == Sha256::digest(expected) => None,
+        _ => {
+            error!("Rejected a key administration request without a valid admin token");
+
+            let response = Json(serde_json::json!({ "error": "A valid admin bearer token is required" }));
+            Some((StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")], response).into_response())
+        }
+    }
+}
+
+fn error_response(e: KeyStoreError) -> Response {
+    error!("{}", e);
+
+    let status = match e {
+        KeyStoreError::UnknownKey(_) => StatusCode::NOT_FOUND,
+        KeyStoreError::InvalidTransition(_) => StatusCode::CONFLICT,
+        KeyStoreError::Io(_) | KeyStoreError::Format(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    let response = Json(serde_json::json!({ "error": e.to_string() }));
+    (status, response).into_response()
+}
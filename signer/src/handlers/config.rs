@@ -1,42 +1,310 @@
-use axum::{Json, http::StatusCode};
+use axum::{Json, extract::State, http::StatusCode};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use std::sync::RwLock;
+use chrono::Utc;
 use once_cell::sync::Lazy;
 use utoipa::ToSchema;
-use tracing::{info};
+use tracing::{info, error};
+use crate::attribute::AttributeType;
+use crate::cryptosuite::Cryptosuite;
+use crate::keystore::KeyAlgorithm;
+use crate::jsonld::context::{Context, ContextLoader};
+use crate::state::AppState;
+use crate::proof::{self, PROOF_MEMBER};
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Default)]
 pub struct ConfigRequest {
     pub entity_type: String,
     pub properties_to_sign: Vec<String>,
+    /// Context against which `entity_type` and `properties_to_sign` are
+    /// expanded, before the NGSI-LD core context
+    #[serde(rename = "@context", default)]
+    pub context: Option<Value>,
+    /// Attribute types signed besides `properties_to_sign`, e.g.
+    /// `["Relationship", "GeoProperty"]`; with neither, every attribute is signed
+    #[serde(default)]
+    pub attribute_types: Vec<AttributeType>,
+    /// Cryptosuite of the proofs, `eddsa-rdfc-2022` by default
+    #[serde(default)]
+    pub cryptosuite: Cryptosuite,
+    /// Key algorithm for ECDSA suites (`P-256` by default, or `P-384`)
+    #[serde(default)]
+    pub key_algorithm: Option<KeyAlgorithm>,
+    /// Further proofs attached next to the main one, forming a proof set,
+    /// e.g. an `mldsa65-rdfc-2024` proof alongside an Ed25519 one
+    #[serde(default)]
+    pub additional_proofs: Vec<ProofSuite>,
+    /// Endorse the proofs already on an attribute that still verify with
+    /// proofs referencing them through `previousProof`, instead of replacing
+    /// them
+    #[serde(default)]
+    pub chain_proofs: bool,
+    /// Lifetime of the proofs in seconds, stamped as their `expires`; proofs
+    /// do not expire by default
+    #[serde(default)]
+    pub proof_validity_seconds: Option<u64>,
+    /// Whether proofs seal each attribute (default), the entity as a whole or both
+    #[serde(default)]
+    pub sealing: SealingMode,
+}
+
+/// What the proofs of an entity type seal.
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SealingMode {
+    /// One proof per attribute in `properties_to_sign`.
+    #[default]
+    Attributes,
+    /// A single entity-level `ngsildproof` over the id, type and every
+    /// attribute, so that added or removed attributes are detected.
+    Entity,
+    /// Both attribute and entity-level proofs.
+    Both,
+}
+
+impl SealingMode {
+    pub fn seals_attributes(&self) -> bool {
+        matches!(self, SealingMode::Attributes | SealingMode::Both)
+    }
+
+    pub fn seals_entity(&self) -> bool {
+        matches!(self, SealingMode::Entity | SealingMode::Both)
+    }
+}
+
+/// A cryptosuite and, for ECDSA suites, the key algorithm of its proofs.
+#[derive(Deserialize, ToSchema, Clone, Copy, Debug)]
+pub struct ProofSuite {
+    pub cryptosuite: Cryptosuite,
+    #[serde(default)]
+    pub key_algorithm: Option<KeyAlgorithm>,
 }
 
 #[derive(Serialize, Clone)]
 pub struct ConfigEntry {
     pub entity_type: String,
+    /// Expanded IRI of `entity_type`, under which the entry is stored
+    pub type_iri: String,
+    pub context: Option<Value>,
     pub properties_to_sign: Vec<String>,
+    pub attribute_types: Vec<AttributeType>,
+    pub cryptosuite: Cryptosuite,
+    pub key_algorithm: KeyAlgorithm,
+    pub additional_proofs: Vec<(Cryptosuite, KeyAlgorithm)>,
+    pub chain_proofs: bool,
+    pub proof_validity_seconds: Option<u64>,
+    pub sealing: SealingMode,
 }
 
-// Global config store: entity_type -> ConfigEntry
+impl ConfigEntry {
+    /// Lifetime of the proofs, checked to fit in a timestamp by `/config`.
+    pub fn proof_validity(&self) -> Option<chrono::Duration> {
+        self.proof_validity_seconds.map(|secs| chrono::Duration::seconds(secs as i64))
+    }
+
+    /// Attributes of `entity` to sign: those named by `properties_to_sign`,
+    /// as given there, and those of one of `attribute_types`, or every
+    /// attribute when both are empty.
+    pub fn attributes_to_sign(&self, entity: &Map<String, Value>) -> Vec<String> {
+        let selected = |value: &Value| match value {
+            _ if self.properties_to_sign.is_empty() && self.attribute_types.is_empty() => {
+                value.is_object() || value.as_array().is_some_and(|a| a.iter().all(Value::is_object))
+            }
+            // Multi-attributes are arrays of instances
+            Value::Array(instances) => instances.iter().any(|instance| self.has_attribute_type(instance)),
+            other => self.has_attribute_type(other),
+        };
+
+        let mut attributes = self.properties_to_sign.clone();
+        attributes.extend(entity.iter()
+            .filter(|(key, _)| *key != "@context" && *key != PROOF_MEMBER)
+            .filter(|(key, value)| !self.properties_to_sign.contains(key) && selected(value))
+            .map(|(key, _)| key.clone()));
+        attributes
+    }
+
+    fn has_attribute_type(&self, attribute: &Value) -> bool {
+        AttributeType::of(attribute).is_some_and(|attribute_type| self.attribute_types.contains(&attribute_type))
+    }
+
+    /// Cryptosuite and key algorithm of every proof to attach, the main one first.
+    pub fn proof_suites(&self) -> Vec<(Cryptosuite, KeyAlgorithm)> {
+        let mut suites = vec![(self.cryptosuite, self.key_algorithm)];
+        suites.extend(self.additional_proofs.iter().copied());
+        suites
+    }
+}
+
+// Global config store: expanded entity type IRI -> ConfigEntry
 pub static CONFIG_STORE: Lazy<RwLock<HashMap<String, ConfigEntry>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
+/// Finds the configuration of an entity by the expanded IRI of its type, so
+/// that `Store` under one `@context` and its full IRI under another match the
+/// same entry. The `properties_to_sign` of the returned entry are given in the
+/// terms of the entity.
+///
+/// The entity's own `@context` applies, else `payload_context`, that of the
+/// enclosing notification.
+pub fn lookup(entity: &Map<String, Value>, payload_context: Option<&Value>, loader: &ContextLoader) -> Option<ConfigEntry> {
+    let entity_type = entity.get("type").and_then(Value::as_str)?;
+    let entity_context = entity.get("@context").or(payload_context);
+
+    let active = Context::ngsi_ld(entity_context, loader)
+        .or_else(|e| {
+            error!("Cannot process the @context of entity type '{}', using the core context: {}", entity_type, e);
+            Context::ngsi_ld(None, loader)
+        })
+        .ok()?;
+    let type_iri = active.expand_iri(entity_type, true)?;
+
+    let mut config = CONFIG_STORE.read().unwrap().get(&type_iri).cloned()?;
+
+    // Checked by /config, which may have used another cache directory
+    let config_active = match Context::ngsi_ld(config.context.as_ref(), loader) {
+        Ok(context) => context,
+        Err(e) => {
+            error!("Cannot process the @context of the configuration of '{}': {}", config.entity_type, e);
+            return Some(config);
+        }
+    };
+
+    let terms = Terms { config: &config_active, entity: &active };
+    config.properties_to_sign = config.properties_to_sign.iter()
+        .map(|property| terms.localize(entity, property))
+        .collect();
+    Some(config)
+}
+
+/// The active contexts of a configuration and of an entity.
+struct Terms<'a> {
+    config: &'a Context,
+    entity: &'a Context,
+}
+
+impl Terms<'_> {
+    /// A configured attribute name, or each name of a sub-attribute path, in
+    /// the terms of `entity`. Names without a counterpart are kept as given.
+    fn localize(&self, entity: &Map<String, Value>, property: &str) -> String {
+        if let Some(key) = self.find(entity, property) {
+            return key.clone();
+        }
+
+        let path = proof::attribute_path(entity, property);
+        let mut level = Some(entity);
+        let localized: Vec<String> = path.iter()
+            .map(|name| {
+                let key = level.and_then(|attributes| self.find(attributes, name)).cloned().unwrap_or_else(|| name.clone());
+                // Sub-attributes of a multi-attribute are looked up in its first instance
+                level = level.and_then(|attributes| attributes.get(&key))
+                    .and_then(|value| match value {
+                        Value::Array(instances) => instances.first(),
+                        other => Some(other),
+                    })
+                    .and_then(Value::as_object);
+                key
+            })
+            .collect();
+
+        match localized == path {
+            true => property.to_string(),
+            false => proof::attribute_pointer(&localized),
+        }
+    }
+
+    /// Key of `attributes` named `name`, or expanding to the same IRI.
+    fn find<'m>(&self, attributes: &'m Map<String, Value>, name: &str) -> Option<&'m String> {
+        if let Some((key, _)) = attributes.get_key_value(name) {
+            return Some(key);
+        }
+
+        let iri = self.config.expand_iri(name, true)?;
+        attributes.keys().find(|key| self.entity.expand_iri(key, true).as_ref() == Some(&iri))
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/config",
     request_body = ConfigRequest,
-    responses((status = 200, description = "Config stored"))
+    responses(
+        (status = 200, description = "Config stored"),
+        (status = 400, description = "Key algorithm not usable with the cryptosuite, suite not usable in a proof set or chain, bbs-2023 sealing a whole entity, proof validity out of range, or unknown @context")
+    )
 )]
-pub async fn config_handler(Json(config): Json<ConfigRequest>) -> StatusCode {
+pub async fn config_handler(State(state): State<AppState>, Json(config): Json<ConfigRequest>) -> StatusCode {
     info!("Calling config_handler method to manage /config endpoint");
 
+    let key_algorithm = config.key_algorithm.unwrap_or(config.cryptosuite.default_algorithm());
+    if !config.cryptosuite.supports(key_algorithm) {
+        error!("Cryptosuite '{}' cannot be used with {} keys",
+            config.cryptosuite.as_str(), key_algorithm.as_str());
+        return StatusCode::BAD_REQUEST;
+    }
+
+    let mut additional_proofs = Vec::new();
+    for proof in &config.additional_proofs {
+        let algorithm = proof.key_algorithm.unwrap_or(proof.cryptosuite.default_algorithm());
+        if !proof.cryptosuite.supports(algorithm) {
+            error!("Cryptosuite '{}' cannot be used with {} keys",
+                proof.cryptosuite.as_str(), algorithm.as_str());
+            return StatusCode::BAD_REQUEST;
+        }
+        additional_proofs.push((proof.cryptosuite, algorithm));
+    }
+
+    // A derived bbs-2023 proof discloses part of the attribute, which the
+    // other proofs of a set or chain would no longer cover
+    let has_bbs = config.cryptosuite == Cryptosuite::Bbs2023
+        || additional_proofs.iter().any(|(suite, _)| *suite == Cryptosuite::Bbs2023);
+    if has_bbs && (!additional_proofs.is_empty() || config.chain_proofs) {
+        error!("Cryptosuite 'bbs-2023' cannot be part of a proof set or chain");
+        return StatusCode::BAD_REQUEST;
+    }
+
+    // Derived proofs are made per attribute
+    if has_bbs && config.sealing.seals_entity() {
+        error!("Cryptosuite 'bbs-2023' cannot seal a whole entity");
+        return StatusCode::BAD_REQUEST;
+    }
+
+    if let Some(secs) = config.proof_validity_seconds {
+        let in_range = i64::try_from(secs).ok()
+            .and_then(chrono::Duration::try_seconds)
+            .is_some_and(|validity| Utc::now().checked_add_signed(validity).is_some());
+        if !in_range {
+            error!("Proof validity of {} seconds is out of range", secs);
+            return StatusCode::BAD_REQUEST;
+        }
+    }
+
+    let active = match Context::ngsi_ld(config.context.as_ref(), &state.contexts) {
+        Ok(context) => context,
+        Err(e) => {
+            error!("Cannot process the @context of entity type '{}': {}", config.entity_type, e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    let type_iri = active.expand_iri(&config.entity_type, true).unwrap_or_else(|| config.entity_type.clone());
+    info!("Entity type '{}' expands to {}", config.entity_type, type_iri);
+
     let mut store = CONFIG_STORE.write().unwrap();
     store.insert(
-        config.entity_type.clone(),
+        type_iri.clone(),
         ConfigEntry {
             entity_type: config.entity_type,
+            type_iri,
+            context: config.context,
             properties_to_sign: config.properties_to_sign,
+            attribute_types: config.attribute_types,
+            cryptosuite: config.cryptosuite,
+            key_algorithm,
+            additional_proofs,
+            chain_proofs: config.chain_proofs,
+            proof_validity_seconds: config.proof_validity_seconds,
+            sealing: config.sealing,
         },
     );
     StatusCode::OK
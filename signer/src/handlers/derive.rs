@@ -0,0 +1,122 @@
+use axum::{Json, extract::State, http::StatusCode, response::{IntoResponse, Response}};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use tracing::{info, error};
+use crate::bbs2023;
+use crate::cryptosuite::Cryptosuite;
+use crate::jsonld::select::parse_pointer;
+use crate::proof::{self, PROOF_MEMBER};
+use crate::state::AppState;
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeriveRequest {
+    /// Entity whose attributes carry `bbs-2023` base proofs
+    pub document: Value,
+    /// JSON pointers into the entity of the values to disclose, e.g. `/temperature/value`
+    #[serde(rename = "selectivePointers")]
+    pub selective_pointers: Vec<String>,
+    /// Data the derived proofs are bound to, such as a nonce of the verifier
+    #[serde(rename = "presentationHeader", default)]
+    pub presentation_header: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/derive",
+    request_body = DeriveRequest,
+    responses(
+        (status = 200, description = "Entity with the disclosed values and their derived proofs", body = Value),
+        (status = 400, description = "Invalid pointer or proof")
+    )
+)]
+pub async fn derive_handler(
+    State(state): State<AppState>,
+    Json(request): Json<DeriveRequest>,
+) -> Result<Json<Value>, Response> {
+    info!("Calling derive_handler method to manage /derive endpoint");
+
+    let entity = request.document.as_object()
+        .ok_or_else(|| bad_request("'document' must be an entity object".into()))?;
+    let entity_id = entity.get("id").and_then(Value::as_str).unwrap_or_default();
+    let entity_type = entity.get("type").and_then(Value::as_str).unwrap_or_default();
+
+    // Pointers are grouped by the attribute they point into
+    let mut pointers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for pointer in &request.selective_pointers {
+        let tokens = parse_pointer(pointer).map_err(|e| bad_request(e.to_string()))?;
+        match tokens.first().map(String::as_str) {
+            None | Some("id" | "type" | "@context") => {}
+            Some(name) => pointers.entry(name.to_string()).or_default().push(pointer.clone()),
+        }
+    }
+
+    let mut derived = Map::new();
+    for member in ["@context", "id", "type"] {
+        if let Some(value) = entity.get(member) {
+            derived.insert(member.into(), value.clone());
+        }
+    }
+
+    let presentation_header = request.presentation_header.unwrap_or_default();
+
+    for (name, attribute_pointers) in pointers {
+        let attribute = entity.get(&name).and_then(Value::as_object)
+            .ok_or_else(|| bad_request(format!("Entity '{}' has no attribute '{}'", entity_id, name)))?;
+
+        let proof_content = attribute.get(PROOF_MEMBER)
+            .and_then(|p| p.get("proof"))
+            .and_then(Value::as_object)
+            .filter(|p| p.get("cryptosuite").and_then(Value::as_str) == Some(Cryptosuite::Bbs2023.as_str()));
+
+        let proof_content = match proof_content {
+            Some(p) => p,
+            // Attributes without a bbs-2023 proof can only be disclosed whole
+            None if attribute_pointers.iter().all(|p| parse_pointer(p).is_ok_and(|t| t.len() == 1)) => {
+                derived.insert(name.clone(), Value::Object(attribute.clone()));
+                continue;
+            }
+            None => {
+                return Err(bad_request(format!(
+                    "Attribute '{}' has no bbs-2023 proof and can only be disclosed whole", name
+                )));
+            }
+        };
+
+        let proof_value = proof_content.get("proofValue").and_then(Value::as_str).unwrap_or_default();
+        if !bbs2023::is_base_proof(proof_value) {
+            return Err(bad_request(format!("Attribute '{}' does not carry a bbs-2023 base proof", name)));
+        }
+
+        let attribute_content = proof::sealed_content(attribute)
+            .map_err(|e| bad_request(format!("Cannot canonicalize attribute '{}': {}", name, e)))?;
+        let document = proof::attribute_document(entity_id, entity_type, std::slice::from_ref(&name), &attribute_content);
+        let (document, _) = proof::with_proof_context(Cryptosuite::Bbs2023, &document, &proof::proof_options(proof_content));
+
+        let (revealed, derived_value) = bbs2023::derive(
+            &document,
+            proof_value,
+            &attribute_pointers,
+            presentation_header.as_bytes(),
+            &state.contexts,
+        )
+        .map_err(|e| bad_request(format!("Cannot derive a proof for attribute '{}': {}", name, e)))?;
+
+        let mut revealed_attribute = revealed.get(&name).cloned().unwrap_or_else(|| Value::Object(Map::new()));
+        let mut derived_proof = attribute[PROOF_MEMBER].clone();
+        derived_proof["proof"]["proofValue"] = Value::String(derived_value);
+        revealed_attribute[PROOF_MEMBER] = derived_proof;
+
+        derived.insert(name, revealed_attribute);
+    }
+
+    Ok(Json(Value::Object(derived)))
+}
+
+fn bad_request(msg: String) -> Response {
+    error!("{}", msg);
+
+    let response = Json(serde_json::json!({ "error": msg }));
+    (StatusCode::BAD_REQUEST, response).into_response()
+}
@@ -0,0 +1,155 @@
+use axum::{Json, extract::{Query, State}};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+use tracing::{info};
+use crate::keystore::{KeyStatus, KeyStore, ServiceKey};
+use crate::multikey;
+use crate::state::AppState;
+
+const DID_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+const MULTIKEY_CONTEXT: &str = "https://w3id.org/security/multikey/v1";
+const JWS_2020_CONTEXT: &str = "https://w3id.org/security/suites/jws-2020/v1";
+
+#[derive(Deserialize, ToSchema, Clone, Copy, Default, PartialEq)]
+pub enum VerificationMethodType {
+    #[default]
+    Multikey,
+    JsonWebKey2020,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct KeysQuery {
+    /// Representation of the public keys, `Multikey` by default
+    #[serde(rename = "type", default)]
+    pub method_type: VerificationMethodType,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub controller: String,
+    #[serde(rename = "publicKeyMultibase", skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+    #[serde(rename = "publicKeyJwk", skip_serializing_if = "Option::is_none")]
+    pub public_key_jwk: Option<Value>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PublishedKey {
+    #[serde(flatten)]
+    pub method: VerificationMethod,
+    pub status: KeyStatus,
+    pub created: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retired: Option<String>,
+    /// Retired keys are only accepted for verification until this instant
+    #[serde(rename = "verifiableUntil", skip_serializing_if = "Option::is_none")]
+    pub verifiable_until: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct KeysResponse {
+    pub keys: Vec<PublishedKey>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/keys",
+    params(KeysQuery),
+    responses((status = 200, description = "Active and retired verification keys", body = KeysResponse))
+)]
+pub async fn keys_handler(
+    State(state): State<AppState>,
+    Query(query): Query<KeysQuery>,
+) -> Json<KeysResponse> {
+    info!("Calling keys_handler method to manage /keys endpoint");
+
+    let key_store = state.key_store.read().unwrap();
+
+    let keys = key_store.verifiable_keys()
+        .map(|key| published_key(&state, &key_store, key, query.method_type))
+        .collect();
+
+    Json(KeysResponse { keys })
+}
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/did.json",
+    params(KeysQuery),
+    responses((status = 200, description = "did:web document of the service", body = Value))
+)]
+pub async fn did_document_handler(
+    State(state): State<AppState>,
+    Query(query): Query<KeysQuery>,
+) -> Json<Value> {
+    info!("Calling did_document_handler method to manage /.well-known/did.json endpoint");
+
+    Json(did_document(&state, query.method_type))
+}
+
+/// Builds the DID document listing every key of the service as an assertion method.
+pub fn did_document(state: &AppState, method_type: VerificationMethodType) -> Value {
+    let key_store = state.key_store.read().unwrap();
+
+    let methods: Vec<VerificationMethod> = key_store.verifiable_keys()
+        .map(|key| verification_method(state, key, method_type))
+        .collect();
+    let ids: Vec<&String> = methods.iter().map(|m| &m.id).collect();
+
+    let key_context = match method_type {
+        VerificationMethodType::Multikey => MULTIKEY_CONTEXT,
+        VerificationMethodType::JsonWebKey2020 => JWS_2020_CONTEXT,
+    };
+
+    serde_json::json!({
+        "@context": [DID_CONTEXT, key_context],
+        "id": state.issuer,
+        "verificationMethod": methods,
+        "assertionMethod": ids
+    })
+}
+
+/// Describes a key of the service together with its lifecycle information.
+pub fn published_key(
+    state: &AppState,
+    key_store: &KeyStore,
+    key: &ServiceKey,
+    method_type: VerificationMethodType,
+) -> PublishedKey {
+    PublishedKey {
+        method: verification_method(state, key, method_type),
+        status: key.status,
+        created: key.created.clone(),
+        retired: key.retired.map(|ts| ts.to_rfc3339()),
+        verifiable_until: key_store.verifiable_until(key).map(|ts| ts.to_rfc3339()),
+    }
+}
+
+fn verification_method(
+    state: &AppState,
+    key: &ServiceKey,
+    method_type: VerificationMethodType,
+) -> VerificationMethod {
+    let public_key = key.public_key();
+
+    let (type_field, public_key_multibase, public_key_jwk) = match method_type {
+        VerificationMethodType::Multikey => {
+            ("Multikey", Some(multikey::encode(&public_key)), None)
+        }
+        VerificationMethodType::JsonWebKey2020 => {
+            ("JsonWebKey2020", None, Some(multikey::jwk(&public_key)))
+        }
+    };
+
+    VerificationMethod {
+        id: state.verification_method(&key.id),
+        type_field: type_field.to_string(),
+        controller: state.issuer.clone(),
+        public_key_multibase,
+        public_key_jwk,
+    }
+}
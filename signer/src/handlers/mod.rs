@@ -2,3 +2,6 @@ pub mod version;
 pub mod sign;
 pub mod verify;
 pub mod config;
+pub mod keys;
+pub mod admin;
+pub mod derive;
@@ -1,14 +1,20 @@
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::{Json, extract::{Query, State}, http::StatusCode, response::{IntoResponse, Response}};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use rand::rngs::OsRng;
-use rand::RngCore;
-use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, SecretKey};
-use base64::{engine::general_purpose::STANDARD, Engine as _};
-use chrono::Utc;
+use serde_json::{Map, Value};
+use chrono::{Duration, Utc};
 //use utoipa::ToSchema;
-use crate::handlers::config::CONFIG_STORE;
+use crate::bbs2023::{self, Bbs2023Error};
+use crate::cryptosuite::Cryptosuite;
+use crate::handlers::config::{self, ConfigEntry};
+use crate::handlers::verify::{self, FreshnessPolicy, VerificationStatus};
+use crate::jsonld::JsonLdError;
+use crate::keystore::ServiceKey;
+use crate::proof::{self, PROOF_MEMBER};
+use crate::representation::{self, Representation};
+use crate::resolver::KeyResolver;
+use crate::state::AppState;
 use tracing::{info, error};
+use utoipa::{IntoParams, ToSchema};
 
 
 /*#[derive(Deserialize, ToSchema)]
@@ -43,15 +49,34 @@ struct ProofContent {
     proof_value: String,
 }
 
+#[derive(Deserialize, IntoParams, Default)]
+pub struct SignQuery {
+    /// Representation of the signed entities, that of each received entity by default
+    pub options: Option<Representation>,
+}
+
 #[utoipa::path(
     post,
     path = "/sign",
     request_body = Value,
-    responses((status = 200, body = Value), (status = 405, description = "No configuration found"))
+    params(SignQuery),
+    responses(
+        (status = 200, description = "The notification with its entities signed", body = Value),
+        (status = 400, description = "No entities to sign, or an attribute cannot be canonicalized"),
+        (status = 428, description = "No signing configuration found for the type of an entity"),
+        (status = 500, description = "The signing key is unavailable or failed to sign")
+    )
 )]
-pub async fn sign_handler(Json(mut doc): Json<Value>) -> impl IntoResponse {
+pub async fn sign_handler(
+    State(state): State<AppState>,
+    Query(query): Query<SignQuery>,
+    Json(mut doc): Json<Value>,
+) -> Result<Json<Value>, Response> {
     info!("Calling sign_handler method to manage /sign endpoint");
 
+    // Entities may rely on the @context of the enclosing notification
+    let payload_context = doc.get("@context").cloned();
+
     // Check if "data" is present and is an array
     let data_array = match doc.get_mut("data").and_then(Value::as_array_mut) {
         Some(arr) if !arr.is_empty() => arr,
@@ -69,81 +94,7 @@ pub async fn sign_handler(Json(mut doc): Json<Value>) -> impl IntoResponse {
     info!("Signing {} entities", data_array.len());
 
     for entity in data_array.iter_mut() {
-        let entity_id = entity.get("id").and_then(Value::as_str).unwrap_or_default().to_string();
-        let entity_type = entity.get("type").and_then(Value::as_str).unwrap_or_default().to_string();
-
-        info!("Reading config store...");
-
-        let config = {
-            let store = CONFIG_STORE.read().unwrap();
-            store.get(&entity_type).cloned()
-        };
-
-        info!("Got config: {:?}", config.as_ref().map(|c| &c.properties_to_sign));
-
-
-        let keys_to_sign: Vec<String> = match config {
-            None => {
-                error!("No signing configuration found for entity type '{}'. \
-                    You must POST to /config first to set the configuration.",
-                    entity_type);
-
-                let msg = format!(
-                    "No signing configuration found for entity type '{}'. \
-                    You must POST to /config first to set the configuration.",
-                    entity_type
-                );
-
-                let response = Json(serde_json::json!({ "error": msg }));
-                return Err((StatusCode::PRECONDITION_REQUIRED, response).into_response());
-            }
-
-            Some(cfg) if cfg.properties_to_sign.is_empty() => {
-                // Sign all top-level object fields
-                let obj = match entity.as_object() {
-                    Some(o) => o,
-                    None => {
-                        error!("Each item in 'data' must be a JSON object.");
-
-                        let response = Json(serde_json::json!({
-                            "error": "Each item in 'data' must be a JSON object."
-                    }));
-        
-                        return Err((StatusCode::BAD_REQUEST, response).into_response());
-                    }
-                };
-
-                obj.iter()
-                    .filter(|(_, v)| v.is_object())
-                    .map(|(k, _)| k.clone())
-                    .collect()
-            }
-            Some(cfg) => cfg.properties_to_sign.clone(),
-        };
-
-        info!("Signing {} properties for entity type '{}'", keys_to_sign.len(), entity_type);
-
-        // Generate ephemeral signing key (temporary)
-        let mut secret_bytes = [0u8; 32];
-        OsRng.fill_bytes(&mut secret_bytes);
-        let secret_key = SecretKey::from(secret_bytes);
-        let signing_key = SigningKey::from_bytes(&secret_key);
-        let _verifying_key: VerifyingKey = signing_key.verifying_key();
-
-        for key in keys_to_sign {
-            if let Some(parent) = entity.as_object_mut() {
-                if let Some(target) = parent.get(&key).and_then(Value::as_object) {
-                    let to_sign = serde_json::to_vec(target).unwrap();
-                    let signature = signing_key.sign(&to_sign);
-                    let proof = build_proof(&entity_id, &entity_type, &signature);
-
-                    if let Some(Value::Object(signed_section)) = parent.get_mut(&key) {
-                        signed_section.insert("ngsildproof".into(), proof);
-                    }
-                }
-            }
-        }
-
+        sign_entity(&state, &query, entity, payload_context.as_ref()).map_err(IntoResponse::into_response)?;
     }
 
     Ok(Json(doc))
@@ -198,22 +149,420 @@ pub async fn sign_handler(Json(mut doc): Json<Value>) -> impl IntoResponse {
     */
 }
 
-fn build_proof(entity_id: &str, entity_type: &str, signature: &Signature) -> Value {
-    let verification_method = "https://example.edu/issuers/565049#key-1";
+/// Outcome of signing one entity of a batch.
+#[derive(Serialize, ToSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityStatus {
+    /// At least one `ngsildproof` was attached.
+    Signed,
+    /// Everything configured to sign already carried valid proofs, which were kept.
+    Unchanged,
+    /// Nothing configured to sign was found in the entity.
+    Skipped,
+    /// The entity could not be signed and is returned as received.
+    Error,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityResult {
+    pub entity_id: String,
+    pub status: EntityStatus,
+    /// Why the entity could not be signed, for entities in `error`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(nullable = false)]
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+pub struct SignBatchResult {
+    /// The notification, with the entities that could be signed signed
+    #[schema(value_type = Object)]
+    pub document: Value,
+    /// Outcome for each entity, in the order of `data`
+    pub results: Vec<EntityResult>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sign/batch",
+    request_body = Value,
+    params(SignQuery),
+    responses(
+        (status = 200, description = "Every entity signed, unchanged or skipped", body = SignBatchResult),
+        (status = 207, description = "Some entities could not be signed", body = SignBatchResult),
+        (status = 400, description = "No entities to sign")
+    )
+)]
+pub async fn sign_batch_handler(
+    State(state): State<AppState>,
+    Query(query): Query<SignQuery>,
+    Json(mut doc): Json<Value>,
+) -> Result<(StatusCode, Json<SignBatchResult>), Response> {
+    info!("Calling sign_batch_handler method to manage /sign/batch endpoint");
+
+    let payload_context = doc.get("@context").cloned();
+
+    let data_array = match doc.get_mut("data").and_then(Value::as_array_mut) {
+        Some(arr) if !arr.is_empty() => arr,
+        _ => {
+            error!("'data' field must be a non-empty array of entities to sign");
+
+            let response = Json(serde_json::json!({
+                "error": "'data' field must be a non-empty array of entities to sign"
+            }));
+            return Err((StatusCode::BAD_REQUEST, response).into_response());
+        }
+    };
+
+    info!("Signing {} entities", data_array.len());
+
+    // An entity is only replaced once signed, so that a failure leaves it as received
+    let mut results = Vec::new();
+    for entity in data_array.iter_mut() {
+        let entity_id = entity.get("id").and_then(Value::as_str).unwrap_or_default().to_string();
+        let mut signed_entity = entity.clone();
+
+        let (status, reason) = match sign_entity(&state, &query, &mut signed_entity, payload_context.as_ref()) {
+            Ok(EntityStatus::Signed) => {
+                *entity = signed_entity;
+                (EntityStatus::Signed, None)
+            }
+            Ok(status) => (status, None),
+            Err(e) => (EntityStatus::Error, Some(e.message)),
+        };
+        results.push(EntityResult { entity_id, status, reason });
+    }
+
+    let status = match results.iter().any(|r| r.status == EntityStatus::Error) {
+        true => StatusCode::MULTI_STATUS,
+        false => StatusCode::OK,
+    };
+    Ok((status, Json(SignBatchResult { document: doc, results })))
+}
+
+/// Why an entity could not be signed.
+pub struct SignError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl IntoResponse for SignError {
+    fn into_response(self) -> Response {
+        (self.status, Json(serde_json::json!({ "error": self.message }))).into_response()
+    }
+}
+
+/// Signs the attributes of an entity, or the entity as a whole, as configured
+/// for its type, and returns whether an `ngsildproof` was attached.
+fn sign_entity(
+    state: &AppState,
+    query: &SignQuery,
+    entity: &mut Value,
+    payload_context: Option<&Value>,
+) -> Result<EntityStatus, SignError> {
+    // Entities are signed in the normalized representation
+    let mut output = query.options;
+    if let Value::Object(obj) = entity {
+        output = output.or(Some(representation::detect(obj)));
+        *obj = representation::normalize(obj);
+    }
+
+    let entity_id = entity.get("id").and_then(Value::as_str).unwrap_or_default().to_string();
+    let entity_type = entity.get("type").and_then(Value::as_str).unwrap_or_default().to_string();
 
-    let proof = serde_json::json!({
+    info!("Reading config store...");
+
+    let config = entity.as_object().and_then(|obj| config::lookup(obj, payload_context, &state.contexts));
+
+    info!("Got config: {:?}", config.as_ref().map(|c| &c.properties_to_sign));
+
+    let suites = config.as_ref().map(ConfigEntry::proof_suites).unwrap_or_default();
+    let chain_proofs = config.as_ref().is_some_and(|c| c.chain_proofs);
+    let proof_validity = config.as_ref().and_then(|c| c.proof_validity());
+    let sealing = config.as_ref().map(|c| c.sealing).unwrap_or_default();
+    let entity_context = entity.get("@context").or(payload_context).cloned();
+
+    let keys_to_sign: Vec<String> = match config {
+        None => {
+            error!("No signing configuration found for entity type '{}'. \
+                You must POST to /config first to set the configuration.",
+                entity_type);
+
+            let msg = format!(
+                "No signing configuration found for entity type '{}'. \
+                You must POST to /config first to set the configuration.",
+                entity_type
+            );
+
+            return Err(SignError { status: StatusCode::PRECONDITION_REQUIRED, message: msg });
+        }
+
+        Some(cfg) => match entity.as_object() {
+            Some(obj) => cfg.attributes_to_sign(obj),
+            None => {
+                error!("Each item in 'data' must be a JSON object.");
+
+                let msg = "Each item in 'data' must be a JSON object.".to_string();
+                return Err(SignError { status: StatusCode::BAD_REQUEST, message: msg });
+            }
+        },
+    };
+
+    info!("Signing {} properties for entity type '{}'", keys_to_sign.len(), entity_type);
+
+    // One signer per proof of the set, the main one first
+    let mut signers = Vec::new();
+    for (suite, algorithm) in suites {
+        let signing_key = match state.signing_key(algorithm) {
+            Ok(key) => key,
+            Err(e) => {
+                error!("No {} signing key available: {}", algorithm.as_str(), e);
+
+                return Err(SignError { status: StatusCode::INTERNAL_SERVER_ERROR, message: e.to_string() });
+            }
+        };
+        let verification_method = state.verification_method(&signing_key.id);
+        let context = suite.proof_context(entity_context.as_ref());
+        signers.push((suite, signing_key, verification_method, context));
+    }
+
+    let keys_to_sign = if sealing.seals_attributes() { keys_to_sign } else { Vec::new() };
+
+    // Each instance of a multi-attribute is signed on its own
+    let mut targets: Vec<(String, Vec<String>, String)> = keys_to_sign.into_iter()
+        .flat_map(|key| {
+            let path = match entity.as_object() {
+                Some(attributes) => proof::attribute_path(attributes, &key),
+                None => vec![key.clone()],
+            };
+            proof::instance_pointers(entity, &path).into_iter()
+                .map(move |pointer| (key.clone(), path.clone(), pointer))
+        })
+        .collect();
+
+    // The entity itself, at the empty path, gets an entity-level ngsildproof
+    if sealing.seals_entity() {
+        targets.push((PROOF_MEMBER.to_string(), Vec::new(), String::new()));
+    }
+
+    let mut signed = 0;
+    let mut kept = 0;
+    let resolver = state.resolver();
+    for (key, path, pointer) in targets {
+        if let Some(target) = entity.pointer(&pointer).and_then(Value::as_object) {
+            // A notification of the service's own update must not be signed
+            // again, or it would loop through the broker
+            if has_current_proofs(state, &entity_id, &entity_type, &path, target, &signers, &resolver) {
+                info!("Attribute '{}' of entity '{}' already carries valid proofs", key, entity_id);
+                kept += 1;
+                continue;
+            }
+
+            let attribute = match proof::sealed_content(target) {
+                Ok(attribute) => attribute,
+                Err(e) => {
+                    error!("Cannot canonicalize attribute '{}' of entity '{}': {}", key, entity_id, e);
+
+                    let msg = format!("Cannot canonicalize attribute '{}' of entity '{}': {}", key, entity_id, e);
+                    return Err(SignError { status: StatusCode::BAD_REQUEST, message: msg });
+                }
+            };
+            let document = proof::attribute_document(&entity_id, &entity_type, &path, &attribute);
+
+            // Proofs already on the attribute are endorsed rather than replaced,
+            // as long as they still hold over its current content
+            let previous: Vec<Map<String, Value>> = match target.get(PROOF_MEMBER).and_then(Value::as_object) {
+                Some(existing) if chain_proofs => {
+                    let results = verify::verify_field(state, &entity_id, &entity_type, &path, target, &FreshnessPolicy::default(), &resolver);
+                    let existing = proof::proof_set(existing);
+                    if results.iter().any(|result| result.status != VerificationStatus::True) {
+                        info!("Dropping the stale proofs of attribute '{}' of entity '{}'", key, entity_id);
+                    }
+
+                    existing.into_iter()
+                        .zip(&results)
+                        .filter(|(_, result)| result.status == VerificationStatus::True)
+                        .map(|(proof, _)| proof.clone())
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+
+            let previous_ids: Vec<Value> = previous.iter().filter_map(|p| p.get("id").cloned()).collect();
+            if previous_ids.len() < previous.len() {
+                error!("A proof of attribute '{}' of entity '{}' has no id and cannot be endorsed", key, entity_id);
+
+                let msg = format!("A proof of attribute '{}' of entity '{}' has no id and cannot be endorsed", key, entity_id);
+                return Err(SignError { status: StatusCode::BAD_REQUEST, message: msg });
+            }
+
+            let previous_proof = match previous_ids.len() {
+                0 => None,
+                1 => Some(previous_ids[0].clone()),
+                _ => Some(Value::Array(previous_ids)),
+            };
+            let document = proof::chained_document(&document, &previous.iter().collect::<Vec<_>>());
+
+            let mut proofs = Vec::new();
+            for (suite, signing_key, verification_method, context) in &signers {
+                let mut options = proof_options(*suite, context.as_ref(), verification_method, proof_validity);
+                if let Some(previous_proof) = &previous_proof {
+                    options["previousProof"] = previous_proof.clone();
+                }
+
+                let proof_value = match sign_attribute(state, *suite, signing_key, &path, &document, &options) {
+                    Ok(proof_value) => proof_value,
+                    Err(AttributeError::Canonicalization(e)) => {
+                        error!("Cannot canonicalize attribute '{}' of entity '{}': {}", key, entity_id, e);
+
+                        let msg = format!("Cannot canonicalize attribute '{}' of entity '{}': {}", key, entity_id, e);
+                        return Err(SignError { status: StatusCode::BAD_REQUEST, message: msg });
+                    }
+                    Err(AttributeError::Signing(e)) => {
+                        error!("Cannot sign attribute '{}' of entity '{}' with key '{}': {}", key, entity_id, signing_key.id, e);
+
+                        let msg = format!("Cannot sign attribute '{}' of entity '{}' with key '{}': {}", key, entity_id, signing_key.id, e);
+                        return Err(SignError { status: StatusCode::INTERNAL_SERVER_ERROR, message: msg });
+                    }
+                };
+                proofs.push((options, proof_value));
+            }
+            let dataset_id = target.get("datasetId").and_then(Value::as_str).map(String::from);
+            let proof = build_proof(&entity_id, &entity_type, dataset_id.as_deref(), previous, proofs);
+
+            if let Some(Value::Object(signed_section)) = entity.pointer_mut(&pointer) {
+                signed_section.insert(PROOF_MEMBER.into(), proof);
+            }
+            signed += 1;
+        }
+    }
+
+    // keyValues cannot carry proofs, so such entities come back concise
+    if let Value::Object(obj) = entity
+        && output.is_some_and(|output| output != Representation::Normalized) {
+        *obj = representation::concise(obj);
+    }
+
+    Ok(match (signed, kept) {
+        (0, 0) => EntityStatus::Skipped,
+        (0, _) => EntityStatus::Unchanged,
+        _ => EntityStatus::Signed,
+    })
+}
+
+/// Whether the proofs on a target all hold for its current value, and include
+/// one by the current key of each configured proof suite.
+fn has_current_proofs(
+    state: &AppState,
+    entity_id: &str,
+    entity_type: &str,
+    path: &[String],
+    target: &Map<String, Value>,
+    signers: &[(Cryptosuite, ServiceKey, String, Option<Value>)],
+    resolver: &dyn KeyResolver,
+) -> bool {
+    let results = verify::verify_field(state, entity_id, entity_type, path, target, &FreshnessPolicy::default(), resolver);
+
+    !results.is_empty()
+        && results.iter().all(|result| result.status == VerificationStatus::True)
+        && signers.iter().all(|(suite, _, verification_method, _)| results.iter().any(|result| {
+            result.cryptosuite.as_deref() == Some(suite.as_str())
+                && result.verification_method.as_deref() == Some(verification_method.as_str())
+        }))
+}
+
+/// Computes the `proofValue` of an attribute document.
+/// Why an attribute could not be signed.
+enum AttributeError {
+    /// The attribute is not a document the service can canonicalize.
+    Canonicalization(JsonLdError),
+    /// The key failed to sign the canonical attribute.
+    Signing(String),
+}
+
+fn sign_attribute(
+    state: &AppState,
+    suite: Cryptosuite,
+    signing_key: &ServiceKey,
+    path: &[String],
+    document: &Value,
+    options: &Value,
+) -> Result<String, AttributeError> {
+    if suite == Cryptosuite::Bbs2023 {
+        // Base proof: each statement is a BBS message the holder may later withhold
+        let (document, options) = proof::with_proof_context(suite, document, options);
+        let mandatory_pointers = bbs2023::mandatory_pointers(path);
+        return bbs2023::sign(&document, &options, signing_key, &mandatory_pointers, &state.contexts)
+            .map_err(|e| match e {
+                Bbs2023Error::JsonLd(e) => AttributeError::Canonicalization(e),
+                e => AttributeError::Signing(e.to_string()),
+            });
+    }
+
+    let to_sign = proof::signing_input(suite, signing_key.algorithm, document, options, &state.contexts)
+        .map_err(AttributeError::Canonicalization)?;
+    let signature = signing_key.sign(&to_sign).map_err(|e| AttributeError::Signing(e.to_string()))?;
+    Ok(proof::encode_proof_value(&signature))
+}
+
+fn proof_options(
+    suite: Cryptosuite,
+    context: Option<&Value>,
+    verification_method: &str,
+    validity: Option<Duration>,
+) -> Value {
+    let created = Utc::now();
+    let mut options = serde_json::json!({
+        "id": proof::new_proof_id(),
+        "type": "DataIntegrityProof",
+        "created": created.to_rfc3339(),
+        "verificationMethod": verification_method,
+        "cryptosuite": suite.as_str(),
+        "proofPurpose": "assertionMethod"
+    });
+
+    if let Some(validity) = validity {
+        options["expires"] = Value::String((created + validity).to_rfc3339());
+    }
+
+    if let Some(context) = context {
+        options["@context"] = context.clone();
+    }
+
+    options
+}
+
+/// Builds the `ngsildproof` of an attribute from the proofs it endorses and
+/// the options and `proofValue` of each of its new proofs; the `datasetId` of
+/// an instance of a multi-attribute is sealed with the entity id and type.
+fn build_proof(
+    entity_id: &str,
+    entity_type: &str,
+    dataset_id: Option<&str>,
+    previous: Vec<Map<String, Value>>,
+    proofs: Vec<(Value, String)>,
+) -> Value {
+    let new_proofs = proofs.into_iter()
+        .map(|(mut options, proof_value)| {
+            options["proofValue"] = Value::String(proof_value);
+            options
+        });
+    let mut proofs: Vec<Value> = previous.into_iter().map(Value::Object).chain(new_proofs).collect();
+
+    // A single proof keeps its object form, several form a proof set
+    let proofs = if proofs.len() == 1 { proofs.remove(0) } else { Value::Array(proofs) };
+
+    let mut proof = serde_json::json!({
         "type": "Property",
         "entityIdSealed": entity_id,
         "entityTypeSealed": entity_type,
-        "proof": {
-            "type": "DataIntegrityProof",
-            "created": Utc::now().to_rfc3339(),
-            "verificationMethod": verification_method,
-            "cryptosuite": "eddsa-rdfc-2022",
-            "proofPurpose": "assertionMethod",
-            "proofValue": STANDARD.encode(signature.to_bytes())
-        }
+        "proof": proofs
     });
 
+    if let Some(dataset_id) = dataset_id {
+        proof["datasetIdSealed"] = Value::String(dataset_id.into());
+    }
+
     proof
 }
@@ -1,28 +1,211 @@
-use axum::Json;
+use axum::{Json, extract::State};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use serde_json::{Value};
-use ed25519_dalek::{VerifyingKey, Signature, Verifier};
-use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 use utoipa::ToSchema;
 use tracing::{info};
+use crate::bbs2023::{self, Bbs2023Error};
+use crate::cryptosuite::Cryptosuite;
+use crate::handlers::config;
+use crate::proof::{self, ProofValueEncoding, PROOF_MEMBER};
+use crate::representation;
+use crate::resolver::{KeyResolver, PublicKey};
+use crate::state::AppState;
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Deserialize, ToSchema, Default)]
 pub struct VerifyRequest {
+    /// A single entity, an array of entities or a notification whose `data`
+    /// array holds the entities
     pub document: Value,
+    /// How the proofs of a proof set combine, `all` by default
+    #[serde(default)]
+    pub policy: ProofSetPolicy,
+    /// Constraints on the age of the proofs, on top of their `expires`
+    #[serde(default)]
+    pub freshness: FreshnessPolicy,
 }
 
-#[derive(Serialize, ToSchema, Debug)]
+/// Outcome required from the proofs of a proof set for the attribute to verify.
+#[derive(Deserialize, ToSchema, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofSetPolicy {
+    /// Every proof must be valid.
+    #[default]
+    All,
+    /// At least one proof must be valid.
+    Any,
+}
+
+/// Time constraints on the proofs of a `/verify` request.
+#[derive(Deserialize, ToSchema, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FreshnessPolicy {
+    /// Maximum age of a proof in seconds, from its `created`
+    #[serde(default)]
+    pub max_age: Option<u64>,
+    /// Tolerance in seconds for clock differences with the signer
+    #[serde(default)]
+    pub clock_skew: u64,
+    /// Reject proofs whose `created` lies in the future
+    #[serde(default)]
+    pub reject_future: bool,
+}
+
+impl FreshnessPolicy {
+    /// Checks that a proof is within its validity period at `now`: not past
+    /// its `expires` and, as required by the policy, neither too old nor
+    /// created in the future.
+    pub fn check(&self, proof: &Map<String, Value>, now: DateTime<Utc>) -> Result<(), FailureReason> {
+        let skew = seconds(self.clock_skew);
+        let timestamp = |name: &str| -> Result<Option<DateTime<Utc>>, FailureReason> {
+            match proof.get(name) {
+                None => Ok(None),
+                Some(value) => value.as_str()
+                    .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+                    .map(|ts| Some(ts.with_timezone(&Utc)))
+                    .ok_or(FailureReason::MalformedTimestamp),
+            }
+        };
+
+        // Bounds too far away to be represented are never reached
+        let passed = |time: DateTime<Utc>, delays: &[Duration]| {
+            delays.iter().try_fold(time, |time, delay| time.checked_add_signed(*delay))
+                .is_some_and(|limit| limit < now)
+        };
+
+        if let Some(expires) = timestamp("expires")?
+            && passed(expires, &[skew]) {
+            return Err(FailureReason::Expired);
+        }
+
+        if self.max_age.is_none() && !self.reject_future {
+            return Ok(());
+        }
+
+        let created = timestamp("created")?.ok_or(FailureReason::MalformedTimestamp)?;
+
+        if self.reject_future && now.checked_add_signed(skew).is_some_and(|limit| created > limit) {
+            return Err(FailureReason::CreatedInFuture);
+        }
+
+        if let Some(max_age) = self.max_age
+            && passed(created, &[seconds(max_age), skew]) {
+            return Err(FailureReason::MaxAgeExceeded);
+        }
+
+        Ok(())
+    }
+}
+
+fn seconds(secs: u64) -> Duration {
+    i64::try_from(secs).ok().and_then(Duration::try_seconds).unwrap_or(Duration::MAX)
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, Copy, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum VerificationStatus {
     True,
     False,
     NA,
+    /// The signature holds but the proof is outside its validity period.
+    Expired,
+    /// The configuration of the entity type requires the attribute to be
+    /// signed, but it is absent or carries no proof.
+    Missing,
+}
+
+/// Why a proof did not verify.
+#[derive(Serialize, ToSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureReason {
+    /// The attribute carries no proof.
+    MissingProof,
+    /// The proof has no `proofValue`.
+    MissingProofValue,
+    /// The `cryptosuite` of the proof is missing or not supported.
+    UnknownCryptosuite,
+    /// The `verificationMethod` of the proof does not resolve to a key.
+    UnknownVerificationMethod,
+    /// The key cannot make proofs of the cryptosuite.
+    KeyMismatch,
+    /// The `proofValue` is neither multibase nor base64, or not a valid proof of the suite.
+    MalformedProofValue,
+    /// The signature does not have the length of the signatures of the key.
+    InvalidSignatureLength,
+    /// The proof was sealed for another entity id or type.
+    EntityMismatch,
+    /// A proof named by `previousProof` is missing, comes after the proof or does not verify.
+    BrokenChain,
+    /// The attribute or the proof options cannot be canonicalized.
+    CanonicalizationError,
+    /// The signature does not match: the attribute, its name or the proof
+    /// options changed since signing.
+    InvalidSignature,
+    /// The `created` or `expires` of the proof is not an RFC 3339 timestamp,
+    /// or `created` is missing while the freshness policy needs it.
+    MalformedTimestamp,
+    /// The proof is past its `expires`.
+    Expired,
+    /// The proof is older than the `maxAge` of the freshness policy.
+    MaxAgeExceeded,
+    /// The proof was created after the time of verification.
+    CreatedInFuture,
+    /// The attribute, signed per the configuration of the entity type, is absent.
+    AttributeMissing,
+}
+
+impl FailureReason {
+    fn status(self) -> VerificationStatus {
+        match self {
+            FailureReason::MissingProof | FailureReason::MissingProofValue => VerificationStatus::NA,
+            FailureReason::Expired | FailureReason::MaxAgeExceeded | FailureReason::CreatedInFuture => {
+                VerificationStatus::Expired
+            }
+            FailureReason::AttributeMissing => VerificationStatus::Missing,
+            _ => VerificationStatus::False,
+        }
+    }
+}
+
+/// Outcome of one proof; for an attribute, of the proof that decided its status.
+#[derive(Serialize, ToSchema, Clone, Debug)]
+pub struct ProofResult {
+    pub status: VerificationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<FailureReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cryptosuite: Option<String>,
+    #[serde(rename = "verificationMethod", skip_serializing_if = "Option::is_none")]
+    pub verification_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
 }
 
+impl ProofResult {
+    fn new(proof: &Map<String, Value>, outcome: Result<(), FailureReason>) -> Self {
+        let member = |name: &str| proof.get(name).and_then(Value::as_str).map(String::from);
+
+        ProofResult {
+            status: outcome.map_or_else(FailureReason::status, |_| VerificationStatus::True),
+            reason: outcome.err(),
+            cryptosuite: member("cryptosuite"),
+            verification_method: member("verificationMethod"),
+            created: member("created"),
+        }
+    }
+}
+
+/// Outcomes keyed by entity id, then attribute name.
 #[derive(Serialize, ToSchema)]
 pub struct VerifyResult {
-    pub results: HashMap<String, VerificationStatus>,
+    pub results: HashMap<String, HashMap<String, ProofResult>>,
+    /// Outcome of each proof of the signed attributes, in document order
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub proofs: HashMap<String, HashMap<String, Vec<ProofResult>>>,
+    /// Encoding of the `proofValue` found on each signed attribute
+    #[serde(rename = "proofValueEncodings", skip_serializing_if = "HashMap::is_empty")]
+    pub encodings: HashMap<String, HashMap<String, ProofValueEncoding>>,
 }
 
 #[utoipa::path(
@@ -31,90 +214,386 @@ pub struct VerifyResult {
     request_body = VerifyRequest,
     responses((status = 200, body = VerifyResult))
 )]
-pub async fn verify_handler(Json(payload): Json<VerifyRequest>) -> Json<VerifyResult> {
+pub async fn verify_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyRequest>,
+) -> Json<VerifyResult> {
     info!("Calling verify_handler method to manage /verify endpoint");
 
-    let mut results = HashMap::new();
-
-    const PUBLIC_KEY_B64: &str = "REPLACE_WITH_YOUR_PUBLIC_KEY_BASE64";
-    
-    let public_key_bytes = match STANDARD.decode(PUBLIC_KEY_B64) {
-        Ok(bytes) => bytes,
-        Err(_) => return Json(VerifyResult { results }),
+    let mut result = VerifyResult {
+        results: HashMap::new(),
+        proofs: HashMap::new(),
+        encodings: HashMap::new(),
     };
 
-    let public_key_array: [u8; 32] = match public_key_bytes.try_into() {
-        Ok(arr) => arr,
-        Err(_) => return Json(VerifyResult { results }),
-    };
-    
-    let verifying_key = match VerifyingKey::from_bytes(&public_key_array) {
-        Ok(k) => k,
-        Err(_) => return Json(VerifyResult { results }),
+    let resolver = state.resolver();
+    let verifier = Verifier { state: &state, request: &payload, resolver: &resolver };
+
+    let entities = entities(&payload.document);
+    info!("Verifying {} entities", entities.len());
+
+    for entity in entities {
+        verify_entity(&verifier, &representation::normalize(entity), &mut result);
+    }
+
+    Json(result)
+}
+
+/// Entities of a `/verify` document: a single entity, an array of entities
+/// or the `data` array of a notification.
+fn entities(document: &Value) -> Vec<&Map<String, Value>> {
+    match document {
+        Value::Array(items) => items.iter().filter_map(Value::as_object).collect(),
+        Value::Object(obj) => match obj.get("data") {
+            // An attribute named `data` is an object, or an array of instances without `id`
+            Some(Value::Array(items)) if items.iter().all(|e| e.get("id").is_some()) => {
+                items.iter().filter_map(Value::as_object).collect()
+            }
+            _ => vec![obj],
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// What the attributes of a `/verify` request are checked with.
+struct Verifier<'a> {
+    state: &'a AppState,
+    request: &'a VerifyRequest,
+    resolver: &'a dyn KeyResolver,
+}
+
+/// Adds the outcomes of the attributes of `entity` to `result`.
+fn verify_entity(verifier: &Verifier, entity: &Map<String, Value>, result: &mut VerifyResult) {
+    let entity_id = entity.get("id").and_then(Value::as_str).unwrap_or_default();
+    result.results.entry(entity_id.to_string()).or_default();
+
+    for (key, value) in entity.iter().filter(|(key, _)| *key != "@context" && *key != PROOF_MEMBER) {
+        verify_attribute(verifier, entity, std::slice::from_ref(key), key, value, result);
+    }
+
+    // An entity-level proof seals the entity as a whole, at the empty path
+    if entity.contains_key(PROOF_MEMBER) {
+        verify_target(verifier, entity, &[], PROOF_MEMBER, entity, result);
+    }
+
+    check_required(verifier, entity, result.results.entry(entity_id.to_string()).or_default());
+}
+
+/// Flags the attributes that the configuration of the entity type requires to
+/// be signed, but which are absent from the entity or carry no proof.
+fn check_required(verifier: &Verifier, entity: &Map<String, Value>, results: &mut HashMap<String, ProofResult>) {
+    let payload_context = verifier.request.document.get("@context");
+    let Some(config) = config::lookup(entity, payload_context, &verifier.state.contexts) else {
+        return;
     };
 
-    let obj = match payload.document.as_object() {
-        Some(obj) => obj,
-        None => return Json(VerifyResult { results }),
+    let mut required: Vec<String> = Vec::new();
+    if config.sealing.seals_attributes() {
+        required = config.attributes_to_sign(entity).iter()
+            .map(|property| proof::attribute_path(entity, property).join("."))
+            .collect();
+    }
+    if config.sealing.seals_entity() {
+        required.push(PROOF_MEMBER.to_string());
+    }
+
+    for name in required {
+        // Instances of multi-attributes are reported as `name[datasetId]`
+        let mut instances = results.iter_mut()
+            .filter(|(reported, _)| without_dataset_ids(reported) == name)
+            .peekable();
+
+        if instances.peek().is_none() {
+            let reason = match name == PROOF_MEMBER {
+                true => FailureReason::MissingProof,
+                false => FailureReason::AttributeMissing,
+            };
+            results.insert(name, ProofResult {
+                status: VerificationStatus::Missing,
+                ..ProofResult::new(&Map::new(), Err(reason))
+            });
+            continue;
+        }
+
+        for (_, result) in instances.filter(|(_, result)| result.status == VerificationStatus::NA) {
+            result.status = VerificationStatus::Missing;
+        }
+    }
+}
+
+/// Name reported for an attribute, without the `[datasetId]` of its instances.
+fn without_dataset_ids(name: &str) -> String {
+    let mut depth = 0;
+    name.chars()
+        .filter(|c| {
+            match c {
+                '[' => depth += 1,
+                ']' if depth > 0 => {
+                    depth -= 1;
+                    return false;
+                }
+                _ => {}
+            }
+            depth == 0
+        })
+        .collect()
+}
+
+/// Adds the outcome of the attribute at `path`, and of its sub-attributes, to
+/// `result` under `name`: the dotted path of the attribute (`address.verifiedBy`),
+/// with the `datasetId` of the instances of multi-attributes (`speed[urn:gps]`).
+fn verify_attribute(
+    verifier: &Verifier,
+    entity: &Map<String, Value>,
+    path: &[String],
+    name: &str,
+    value: &Value,
+    result: &mut VerifyResult,
+) {
+    // Each instance of a multi-attribute is checked on its own; the default
+    // instance, without `datasetId`, under the name of the attribute
+    if let Value::Array(instances) = value {
+        for instance in instances.iter().filter(|instance| instance.is_object()) {
+            let name = match instance.get("datasetId").and_then(Value::as_str) {
+                Some(dataset_id) => format!("{}[{}]", name, dataset_id),
+                None => name.to_string(),
+            };
+            verify_attribute(verifier, entity, path, &name, instance, result);
+        }
+        return;
+    }
+
+    let Some(attribute) = value.as_object() else {
+        return;
     };
 
-    for (key, value) in obj {
-        if !value.is_object() {
+    verify_target(verifier, entity, path, name, attribute, result);
+
+    // Sub-properties and sub-relationships are typed objects, like attributes
+    for (key, member) in attribute {
+        if key == PROOF_MEMBER || representation::ATTRIBUTE_MEMBERS.contains(&key.as_str()) || member.get("type").is_none() {
             continue;
         }
 
-        let status = verify_field(value, &verifying_key);
-        results.insert(key.clone(), status);
+        let path: Vec<String> = path.iter().cloned().chain([key.clone()]).collect();
+        verify_attribute(verifier, entity, &path, &format!("{}.{}", name, key), member, result);
+    }
+}
+
+/// Adds the outcome of the proofs of `target`, the attribute or entity at
+/// `path`, to `result` under `name`.
+fn verify_target(
+    verifier: &Verifier,
+    entity: &Map<String, Value>,
+    path: &[String],
+    name: &str,
+    target: &Map<String, Value>,
+    result: &mut VerifyResult,
+) {
+    let entity_id = entity.get("id").and_then(Value::as_str).unwrap_or_default();
+    let entity_type = entity.get("type").and_then(Value::as_str).unwrap_or_default();
+    let request = verifier.request;
+
+    let proof_results = verify_field(
+        verifier.state,
+        entity_id,
+        entity_type,
+        path,
+        target,
+        &request.freshness,
+        verifier.resolver,
+    );
+    result.results.entry(entity_id.to_string()).or_default()
+        .insert(name.to_string(), combine(&proof_results, request.policy));
+
+    let proof_values = target.get(PROOF_MEMBER)
+        .and_then(Value::as_object)
+        .map(proof::proof_set)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|p| p.get("proofValue").and_then(Value::as_str))
+        .filter_map(proof::decode_proof_value);
+
+    // An attribute is reported as base64 as soon as one of its proofs is
+    for (_, encoding) in proof_values {
+        let encodings = result.encodings.entry(entity_id.to_string()).or_default();
+        let entry = encodings.entry(name.to_string()).or_insert(encoding);
+        if encoding == ProofValueEncoding::Base64 {
+            *entry = encoding;
+        }
     }
 
-    Json(VerifyResult { results })
+    if !proof_results.is_empty() {
+        result.proofs.entry(entity_id.to_string()).or_default().insert(name.to_string(), proof_results);
+    }
 }
 
-fn verify_field(value: &Value, verifying_key: &VerifyingKey) -> VerificationStatus {
-    let field_obj = match value.as_object() {
-        Some(obj) => obj,
-        None => return VerificationStatus::NA,
+/// Combines the outcomes of the proofs of an attribute under `policy`,
+/// reporting the details of the proof that decided it.
+fn combine(proof_results: &[ProofResult], policy: ProofSetPolicy) -> ProofResult {
+    let Some(first) = proof_results.first() else {
+        return ProofResult::new(&Map::new(), Err(FailureReason::MissingProof));
+    };
+
+    if proof_results.iter().all(|r| r.status == VerificationStatus::NA) {
+        return first.clone();
+    }
+
+    // Invalid proofs outweigh expired ones, which outweigh proofs without value
+    let failed = proof_results.iter()
+        .find(|r| r.status == VerificationStatus::False)
+        .or_else(|| proof_results.iter().find(|r| r.status == VerificationStatus::Expired))
+        .or_else(|| proof_results.iter().find(|r| r.status != VerificationStatus::True));
+
+    let decisive = match policy {
+        ProofSetPolicy::All => failed.unwrap_or(first),
+        ProofSetPolicy::Any => proof_results.iter()
+            .find(|r| r.status == VerificationStatus::True)
+            .or(failed)
+            .unwrap_or(first),
+    };
+
+    let status = match decisive.status {
+        VerificationStatus::NA => VerificationStatus::False,
+        status => status,
     };
 
-    let proof_obj = match field_obj.get("ngsildproof") {
+    ProofResult { status, ..decisive.clone() }
+}
+
+/// Checks every proof of an attribute; empty when the attribute has none.
+pub(crate) fn verify_field(
+    state: &AppState,
+    entity_id: &str,
+    entity_type: &str,
+    path: &[String],
+    field_obj: &Map<String, Value>,
+    freshness: &FreshnessPolicy,
+    resolver: &dyn KeyResolver,
+) -> Vec<ProofResult> {
+    let proof_obj = match field_obj.get(PROOF_MEMBER) {
         Some(Value::Object(p)) => p,
-        _ => return VerificationStatus::NA,
+        _ => return Vec::new(),
     };
 
-    let proof_value_b64 = match proof_obj.get("proof")
-        .and_then(|p| p.get("proofValue"))
-        .and_then(Value::as_str) {
-            Some(val) => val,
-            None => return VerificationStatus::NA,
+    // The proofs must have been made for this very entity and instance
+    let sealed = proof_obj.get("entityIdSealed").and_then(Value::as_str) == Some(entity_id)
+        && proof_obj.get("entityTypeSealed").and_then(Value::as_str) == Some(entity_type)
+        && proof_obj.get("datasetIdSealed").and_then(Value::as_str)
+            == field_obj.get("datasetId").and_then(Value::as_str);
+
+    let proofs = proof::proof_set(proof_obj);
+
+    // An attribute without a canonical form fails every proof
+    let document = match proof::sealed_content(field_obj) {
+        Ok(attribute) => proof::attribute_document(entity_id, entity_type, path, &attribute),
+        Err(_) => {
+            return proofs.into_iter()
+                .map(|proof_content| ProofResult::new(proof_content, Err(FailureReason::CanonicalizationError)))
+                .collect();
+        }
     };
 
-    let signature_bytes = match STANDARD.decode(proof_value_b64) {
-        Ok(bytes) => bytes,
-        Err(_) => return VerificationStatus::False,
+    let mut results: Vec<ProofResult> = Vec::new();
+    let now = Utc::now();
+
+    // Chains are checked in order: a proof only holds if every proof it
+    // endorses comes before it and holds too
+    for (i, proof_content) in proofs.iter().enumerate() {
+        let previous: Option<Vec<usize>> = proof::previous_proof_ids(proof_content)
+            .into_iter()
+            .map(|id| proofs[..i].iter().position(|p| p.get("id").and_then(Value::as_str) == Some(id)))
+            .collect();
+
+        let outcome = match previous {
+            _ if !sealed => Err(FailureReason::EntityMismatch),
+            Some(previous) if previous.iter().all(|&j| results[j].status == VerificationStatus::True) => {
+                let previous: Vec<&Map<String, Value>> = previous.iter().map(|&j| proofs[j]).collect();
+                verify_proof(state, &document, proof_content, &previous, resolver)
+                    .and_then(|_| freshness.check(proof_content, now))
+            }
+            _ => Err(FailureReason::BrokenChain),
+        };
+
+        results.push(ProofResult::new(proof_content, outcome));
+    }
+
+    results
+}
+
+/// Checks one proof over the attribute document and the proofs it endorses.
+fn verify_proof(
+    state: &AppState,
+    document: &Value,
+    proof_content: &Map<String, Value>,
+    previous: &[&Map<String, Value>],
+    resolver: &dyn KeyResolver,
+) -> Result<(), FailureReason> {
+    // The proof declares how the attribute was canonicalized
+    let suite = match proof_content.get("cryptosuite")
+        .and_then(Value::as_str)
+        .and_then(Cryptosuite::from_name) {
+            Some(suite) => suite,
+            None => return Err(FailureReason::UnknownCryptosuite),
     };
 
-    // Convert Vec<u8> to [u8; 64] for signature
-    let signature_array: [u8; 64] = match signature_bytes.try_into() {
-        Ok(arr) => arr,
-        Err(_) => return VerificationStatus::False,
+    // Select the key named by the proof
+    let public_key = match proof_content.get("verificationMethod")
+        .and_then(Value::as_str)
+        .and_then(|method| resolver.resolve(method)) {
+            Some(key) => key,
+            None => return Err(FailureReason::UnknownVerificationMethod),
     };
 
-    let signature = Signature::from_bytes(&signature_array);
+    if !suite.supports(public_key.algorithm()) {
+        return Err(FailureReason::KeyMismatch);
+    }
 
-    let signed_bytes = match serde_json::to_vec(&field_obj_without_proof(field_obj)) {
-        Ok(data) => data,
-        Err(_) => return VerificationStatus::False,
+    let proof_value = match proof_content.get("proofValue").and_then(Value::as_str) {
+        Some(val) => val,
+        None => return Err(FailureReason::MissingProofValue),
     };
 
-    match verifying_key.verify(&signed_bytes, &signature) {
-        Ok(_) => VerificationStatus::True,
-        Err(_) => VerificationStatus::False,
+    // Multibase per the Data Integrity suites, or base64 for older proofs
+    let signature_bytes = match proof::decode_proof_value(proof_value) {
+        Some((bytes, _)) => bytes,
+        None => return Err(FailureReason::MalformedProofValue),
+    };
+
+    let document = proof::chained_document(document, previous);
+
+    // Base and derived proofs only disclose BBS messages, not a signature of hashData
+    if suite == Cryptosuite::Bbs2023 {
+        let PublicKey::Bls12381G2(bbs_key) = public_key else {
+            return Err(FailureReason::KeyMismatch);
+        };
+
+        let (document, options) = proof::with_proof_context(suite, &document, &proof::proof_options(proof_content));
+        return match bbs2023::verify(&document, &options, proof_value, &bbs_key, &state.contexts) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(FailureReason::InvalidSignature),
+            Err(Bbs2023Error::JsonLd(_)) => Err(FailureReason::CanonicalizationError),
+            Err(_) => Err(FailureReason::MalformedProofValue),
+        };
+    }
+
+    if signature_bytes.len() != public_key.signature_length() {
+        return Err(FailureReason::InvalidSignatureLength);
     }
-}
 
-// Helper to remove ngsildproof before signing
-fn field_obj_without_proof(field: &serde_json::Map<String, Value>) -> Value {
-    let mut cleaned = field.clone();
-    cleaned.remove("ngsildproof");
-    Value::Object(cleaned)
+    let signed_bytes = match proof::signing_input(
+        suite,
+        public_key.algorithm(),
+        &document,
+        &proof::proof_options(proof_content),
+        &state.contexts,
+    ) {
+        Ok(bytes) => bytes,
+        Err(_) => return Err(FailureReason::CanonicalizationError),
+    };
+
+    match public_key.verify(&signed_bytes, &signature_bytes) {
+        true => Ok(()),
+        false => Err(FailureReason::InvalidSignature),
+    }
 }
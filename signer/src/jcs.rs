@@ -0,0 +1,129 @@
+//! JSON Canonicalization Scheme (RFC 8785).
+
+use serde_json::{Number, Value};
+use std::fmt::{self, Write};
+
+/// Why a value has no JCS canonical form.
+#[derive(Debug)]
+pub enum JcsError {
+    /// An integer that an IEEE 754 double, the only JCS number, cannot hold
+    /// exactly.
+    InexactNumber(String),
+}
+
+impl fmt::Display for JcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JcsError::InexactNumber(n) => write!(f, "number {} cannot be represented exactly as a double", n),
+        }
+    }
+}
+
+impl std::error::Error for JcsError {}
+
+/// Serializes `value` in its JCS canonical form: no whitespace, object members
+/// sorted by their UTF-16 code units, minimal string escaping and numbers
+/// formatted as ECMAScript does.
+pub fn canonicalize(value: &Value) -> Result<String, JcsError> {
+    let mut out = String::new();
+    write_value(&mut out, value)?;
+    Ok(out)
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<(), JcsError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&format_number(exact_f64(n)?)),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut members: Vec<(&String, &Value)> = map.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, (key, member)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, member)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+/// The double of a number, rejecting integers it would round, such as
+/// `2^53 + 1`, rather than signing a value other than the one sent.
+fn exact_f64(n: &Number) -> Result<f64, JcsError> {
+    let value = n.as_f64().unwrap_or_default();
+    let integer = n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from));
+
+    match integer {
+        // The cast saturates, so 2^63 and 2^64 do not compare equal to i64::MAX or u64::MAX
+        Some(integer) if value as i128 != integer => Err(JcsError::InexactNumber(n.to_string())),
+        _ => Ok(value),
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < '\u{20}' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript `Number.prototype.toString` of a finite double.
+fn format_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".into();
+    }
+
+    // `{:e}` yields the shortest digits that round-trip, e.g. `1.2345e-7`
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    let k = digits.len() as i32;
+    let n = exponent + 1;
+
+    let formatted = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        let fraction = if k > 1 { format!(".{}", &digits[1..]) } else { String::new() };
+        format!("{}{}e{}{}", &digits[..1], fraction, sign, (n - 1).abs())
+    };
+
+    if value < 0.0 { format!("-{}", formatted) } else { formatted }
+}
@@ -0,0 +1,374 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{info};
+use crate::jsonld::JsonLdError;
+
+/// NGSI-LD core context, implicitly applied to every NGSI-LD payload.
+pub const NGSI_LD_CORE_CONTEXT: &str = "https://uri.etsi.org/ngsi-ld/v1/ngsi-ld-core-context-v1.8.jsonld";
+
+/// Context defining the Data Integrity proof vocabulary.
+pub const DATA_INTEGRITY_CONTEXT: &str = "https://w3id.org/security/data-integrity/v2";
+
+/// Environment variable pointing to a directory of cached remote contexts.
+pub const JSONLD_CONTEXT_DIR_ENV: &str = "JSONLD_CONTEXT_DIR";
+
+/// Contexts shipped with the service, keyed by URL.
+const BUNDLED_CONTEXTS: &[(&str, &str)] = &[
+    (NGSI_LD_CORE_CONTEXT, include_str!("../../contexts/ngsi-ld-core-context-v1.8.jsonld")),
+    (DATA_INTEGRITY_CONTEXT, include_str!("../../contexts/data-integrity-v2.jsonld")),
+];
+
+/// Maximum depth of nested remote context references.
+const MAX_REMOTE_CONTEXTS: usize = 16;
+
+/// Loads remote contexts from a local cache; the signer never dereferences
+/// context URLs over the network.
+///
+/// Besides the bundled contexts, documents are looked up in an optional
+/// directory mirroring the URL layout, e.g.
+/// `https://example.org/ctx/store.jsonld` → `<dir>/example.org/ctx/store.jsonld`.
+#[derive(Clone, Default)]
+pub struct ContextLoader {
+    dir: Option<PathBuf>,
+}
+
+impl ContextLoader {
+    pub fn new() -> Self {
+        ContextLoader::default()
+    }
+
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Returns the `@context` member of the document cached for `url`.
+    pub fn load(&self, url: &str) -> Result<Value, JsonLdError> {
+        let content = match BUNDLED_CONTEXTS.iter().find(|(u, _)| *u == url) {
+            Some((_, content)) => content.to_string(),
+            None => self.load_from_dir(url)?,
+        };
+
+        let document: Value = serde_json::from_str(&content)
+            .map_err(|e| JsonLdError::InvalidContext(format!("{}: {}", url, e)))?;
+
+        document.get("@context")
+            .cloned()
+            .ok_or_else(|| JsonLdError::InvalidContext(format!("{}: no @context member", url)))
+    }
+
+    fn load_from_dir(&self, url: &str) -> Result<String, JsonLdError> {
+        let unknown = || JsonLdError::UnknownContext(url.to_string());
+
+        let dir = self.dir.as_ref().ok_or_else(unknown)?;
+        let relative = url.strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .ok_or_else(unknown)?;
+
+        if relative.split('/').any(|segment| segment.is_empty() || segment.starts_with('.')) {
+            return Err(unknown());
+        }
+
+        let path = dir.join(relative);
+        info!("Loading JSON-LD context {} from {}", url, path.display());
+
+        fs::read_to_string(&path).map_err(|_| unknown())
+    }
+}
+
+/// A term definition of an active context.
+#[derive(Clone, Debug, Default)]
+pub struct TermDefinition {
+    /// IRI mapping; `None` for terms explicitly mapped to `null`.
+    pub iri: Option<String>,
+    pub type_mapping: Option<String>,
+    pub container: Vec<String>,
+    pub language: Option<Option<String>>,
+    /// Scoped context, applied to the values of the term (or to nodes of
+    /// that type when the term is used as a type).
+    pub context: Option<Value>,
+    pub prefix: bool,
+}
+
+impl TermDefinition {
+    pub fn has_container(&self, container: &str) -> bool {
+        self.container.iter().any(|c| c == container)
+    }
+}
+
+/// The active context used during expansion.
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    pub terms: HashMap<String, TermDefinition>,
+    pub vocab: Option<String>,
+    pub default_language: Option<String>,
+    /// Context to revert to when leaving a node with a type-scoped context.
+    pub previous: Option<Box<Context>>,
+}
+
+impl Context {
+    /// Active context of an NGSI-LD payload: its `@context`, if any, followed
+    /// by the NGSI-LD core context, which takes precedence.
+    pub fn ngsi_ld(context: Option<&Value>, loader: &ContextLoader) -> Result<Context, JsonLdError> {
+        let mut contexts = match context {
+            Some(Value::Array(items)) => items.clone(),
+            Some(Value::Null) | None => Vec::new(),
+            Some(other) => vec![other.clone()],
+        };
+        contexts.push(Value::String(NGSI_LD_CORE_CONTEXT.into()));
+
+        Context::default().process(&Value::Array(contexts), loader, true)
+    }
+
+    /// Processes `local` on top of this context (JSON-LD 1.1 Context Processing).
+    ///
+    /// `propagate` is false for type-scoped contexts, which only apply to the
+    /// node they were found on.
+    pub fn process(&self, local: &Value, loader: &ContextLoader, propagate: bool) -> Result<Context, JsonLdError> {
+        let mut result = self.clone();
+
+        if !propagate && result.previous.is_none() {
+            result.previous = Some(Box::new(self.clone()));
+        }
+
+        let mut remote_contexts = Vec::new();
+        result.process_local(local, loader, &mut remote_contexts)?;
+
+        Ok(result)
+    }
+
+    fn process_local(
+        &mut self,
+        local: &Value,
+        loader: &ContextLoader,
+        remote_contexts: &mut Vec<String>,
+    ) -> Result<(), JsonLdError> {
+        let contexts = match local {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+
+        for context in contexts {
+            match context {
+                Value::Null => {
+                    let previous = self.previous.take();
+                    *self = Context { previous, ..Context::default() };
+                }
+                Value::String(url) => {
+                    if remote_contexts.len() >= MAX_REMOTE_CONTEXTS {
+                        return Err(JsonLdError::InvalidContext(format!("{}: too many nested contexts", url)));
+                    }
+                    // A context referenced twice in the same chain is a no-op
+                    if remote_contexts.contains(url) {
+                        continue;
+                    }
+
+                    let loaded = loader.load(url)?;
+                    remote_contexts.push(url.clone());
+                    self.process_local(&loaded, loader, remote_contexts)?;
+                    remote_contexts.pop();
+                }
+                Value::Object(definition) => self.process_definition(definition)?,
+                other => {
+                    return Err(JsonLdError::InvalidContext(format!("invalid local context {}", other)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_definition(&mut self, definition: &Map<String, Value>) -> Result<(), JsonLdError> {
+        if let Some(version) = definition.get("@version")
+            && version.as_f64() != Some(1.1) {
+            return Err(JsonLdError::InvalidContext(format!("unsupported @version {}", version)));
+        }
+
+        if definition.contains_key("@import") {
+            return Err(JsonLdError::InvalidContext("@import is not supported".into()));
+        }
+
+        if let Some(vocab) = definition.get("@vocab") {
+            self.vocab = match vocab {
+                Value::Null => None,
+                Value::String(v) => {
+                    let mut defined = HashMap::new();
+                    Some(self.expand_iri_in(v, true, definition, &mut defined)?.unwrap_or_else(|| v.clone()))
+                }
+                other => return Err(JsonLdError::InvalidContext(format!("invalid @vocab {}", other))),
+            };
+        }
+
+        if let Some(language) = definition.get("@language") {
+            self.default_language = language.as_str().map(str::to_string);
+        }
+
+        let mut defined = HashMap::new();
+        for term in definition.keys() {
+            if matches!(term.as_str(), "@base" | "@version" | "@vocab" | "@language" | "@protected" | "@propagate" | "@direction") {
+                continue;
+            }
+            self.create_term_definition(term, definition, &mut defined)?;
+        }
+
+        Ok(())
+    }
+
+    fn create_term_definition(
+        &mut self,
+        term: &str,
+        local: &Map<String, Value>,
+        defined: &mut HashMap<String, bool>,
+    ) -> Result<(), JsonLdError> {
+        match defined.get(term) {
+            Some(true) => return Ok(()),
+            Some(false) => return Err(JsonLdError::InvalidContext(format!("cyclic definition of '{}'", term))),
+            None => {}
+        }
+        defined.insert(term.to_string(), false);
+
+        if is_keyword(term) {
+            // Keyword aliases cannot be redefined; only `@type` may carry options
+            defined.insert(term.to_string(), true);
+            return Ok(());
+        }
+
+        let value = local.get(term).cloned().unwrap_or(Value::Null);
+        let mut definition = TermDefinition::default();
+
+        definition.iri = match &value {
+            Value::Null => None,
+            Value::Object(map) if map.get("@id") == Some(&Value::Null) => None,
+            Value::String(iri) => self.term_iri(term, iri, local, defined)?,
+            Value::Object(map) => match map.get("@id") {
+                Some(Value::String(iri)) => self.term_iri(term, iri, local, defined)?,
+                Some(other) => return Err(JsonLdError::InvalidContext(format!("invalid @id for '{}': {}", term, other))),
+                None if term.contains(':') => self.expand_iri_in(term, true, local, defined)?,
+                None => match &self.vocab {
+                    Some(vocab) => Some(format!("{}{}", vocab, term)),
+                    None => return Err(JsonLdError::InvalidContext(format!("'{}' does not expand to an IRI", term))),
+                },
+            },
+            other => return Err(JsonLdError::InvalidContext(format!("invalid definition of '{}': {}", term, other))),
+        };
+
+        // Terms ending with a gen-delim character may be used as prefixes
+        definition.prefix = matches!(&value, Value::String(_))
+            && definition.iri.as_ref().is_some_and(|iri| iri.ends_with(['/', '#', ':', '?', '@', '[', ']']));
+
+        if let Value::Object(map) = &value {
+            if let Some(type_mapping) = map.get("@type") {
+                let t = type_mapping.as_str()
+                    .ok_or_else(|| JsonLdError::InvalidContext(format!("invalid @type mapping for '{}'", term)))?;
+                definition.type_mapping = match t {
+                    "@id" | "@vocab" | "@json" | "@none" => Some(t.to_string()),
+                    other => self.expand_iri_in(other, true, local, defined)?,
+                };
+            }
+
+            if let Some(container) = map.get("@container") {
+                definition.container = match container {
+                    Value::String(c) => vec![c.clone()],
+                    Value::Array(items) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+                    other => return Err(JsonLdError::InvalidContext(format!("invalid @container {}", other))),
+                };
+            }
+
+            if let Some(language) = map.get("@language") {
+                definition.language = Some(language.as_str().map(str::to_string));
+            }
+
+            if let Some(context) = map.get("@context") {
+                definition.context = Some(context.clone());
+            }
+
+            if let Some(prefix) = map.get("@prefix").and_then(Value::as_bool) {
+                definition.prefix = prefix;
+            }
+        }
+
+        self.terms.insert(term.to_string(), definition);
+        defined.insert(term.to_string(), true);
+
+        Ok(())
+    }
+
+    fn term_iri(
+        &mut self,
+        term: &str,
+        iri: &str,
+        local: &Map<String, Value>,
+        defined: &mut HashMap<String, bool>,
+    ) -> Result<Option<String>, JsonLdError> {
+        match self.expand_iri_in(iri, true, local, defined)? {
+            Some(e) if e.contains(':') || is_keyword(&e) => Ok(Some(e)),
+            _ => Err(JsonLdError::InvalidContext(format!("'{}' does not expand to an IRI", term))),
+        }
+    }
+
+    /// IRI Expansion while processing a local context, defining the terms it
+    /// depends on first.
+    fn expand_iri_in(
+        &mut self,
+        value: &str,
+        vocab: bool,
+        local: &Map<String, Value>,
+        defined: &mut HashMap<String, bool>,
+    ) -> Result<Option<String>, JsonLdError> {
+        if local.contains_key(value) && defined.get(value) != Some(&true) {
+            self.create_term_definition(value, local, defined)?;
+        }
+
+        if let Some((prefix, _)) = value.split_once(':')
+            && local.contains_key(prefix) && defined.get(prefix) != Some(&true) {
+            self.create_term_definition(prefix, local, defined)?;
+        }
+
+        Ok(self.expand_iri(value, vocab))
+    }
+
+    /// IRI Expansion against the active context. Returns `None` for terms
+    /// explicitly mapped to `null`.
+    pub fn expand_iri(&self, value: &str, vocab: bool) -> Option<String> {
+        if is_keyword(value) {
+            return Some(value.to_string());
+        }
+
+        if vocab && let Some(definition) = self.terms.get(value) {
+            return definition.iri.clone();
+        }
+
+        if let Some((prefix, suffix)) = value.split_once(':') {
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.to_string());
+            }
+
+            if let Some(definition) = self.terms.get(prefix)
+                && let (Some(iri), true) = (&definition.iri, definition.prefix) {
+                return Some(format!("{}{}", iri, suffix));
+            }
+
+            // Already an absolute IRI
+            return Some(value.to_string());
+        }
+
+        if vocab && let Some(v) = &self.vocab {
+            return Some(format!("{}{}", v, value));
+        }
+
+        // No @base support: document-relative IRIs are kept as they are
+        Some(value.to_string())
+    }
+}
+
+pub fn is_keyword(value: &str) -> bool {
+    matches!(
+        value,
+        "@base" | "@container" | "@context" | "@direction" | "@graph" | "@id" | "@import"
+            | "@included" | "@index" | "@json" | "@language" | "@list" | "@nest" | "@none"
+            | "@prefix" | "@propagate" | "@protected" | "@reverse" | "@set" | "@type"
+            | "@value" | "@version" | "@vocab"
+    )
+}
@@ -0,0 +1,379 @@
+use serde_json::{Map, Value};
+use crate::jsonld::context::{is_keyword, Context, ContextLoader};
+use crate::jsonld::JsonLdError;
+
+/// JSON-LD 1.1 Expansion of `element`.
+///
+/// Covers what NGSI-LD payloads and Data Integrity proofs use: remote, inline,
+/// property-scoped and type-scoped contexts, `@vocab`, compact IRIs, type
+/// coercion, language maps, `@list`/`@set` containers and `@json` literals.
+/// Named graphs, `@reverse`, `@nest`, `@included`, index maps and graph
+/// containers are rejected; `@graph` is only accepted at the top level, for
+/// the default graph. A member that maps to no absolute IRI is an error
+/// rather than dropped, so that it cannot escape the signature; only terms
+/// mapped to `null` and keyword-like members are ignored.
+pub fn expand(element: &Value, loader: &ContextLoader) -> Result<Vec<Value>, JsonLdError> {
+    let expanded = expand_element(&Context::default(), None, element, "", &mut Vec::new(), loader)?;
+
+    // A top-level object with only a @graph member is unwrapped
+    let expanded = match expanded {
+        Value::Object(mut map) if map.len() == 1 && map.contains_key("@graph") => {
+            map.remove("@graph").unwrap()
+        }
+        other => other,
+    };
+
+    Ok(match expanded {
+        Value::Null => Vec::new(),
+        Value::Array(items) => items,
+        other => vec![other],
+    })
+}
+
+/// JSON pointers, into `element`, of the node objects that have no `@id` and
+/// therefore become blank nodes.
+pub fn blank_node_pointers(element: &Value, loader: &ContextLoader) -> Result<Vec<String>, JsonLdError> {
+    let mut pointers = Vec::new();
+    expand_element(&Context::default(), None, element, "", &mut pointers, loader)?;
+    Ok(pointers)
+}
+
+fn expand_element(
+    active: &Context,
+    active_property: Option<&str>,
+    element: &Value,
+    pointer: &str,
+    blank_nodes: &mut Vec<String>,
+    loader: &ContextLoader,
+) -> Result<Value, JsonLdError> {
+    let property_definition = active_property.and_then(|p| active.terms.get(p));
+    let property_scoped = property_definition.and_then(|d| d.context.clone());
+
+    match element {
+        Value::Null => Ok(Value::Null),
+
+        Value::Array(items) => {
+            let is_list = property_definition.is_some_and(|d| d.has_container("@list"));
+            let mut result = Vec::new();
+
+            for (i, item) in items.iter().enumerate() {
+                let item_pointer = format!("{}/{}", pointer, i);
+                let expanded = expand_element(active, active_property, item, &item_pointer, blank_nodes, loader)?;
+                match expanded {
+                    // Arrays nested in a list are lists of lists
+                    Value::Array(inner) if is_list => {
+                        result.push(serde_json::json!({ "@list": inner }));
+                    }
+                    Value::Array(inner) => result.extend(inner),
+                    Value::Null => {}
+                    other => result.push(other),
+                }
+            }
+
+            Ok(Value::Array(result))
+        }
+
+        Value::Object(map) => {
+            let expanded = expand_object(active, active_property, property_scoped, map, pointer, blank_nodes, loader)?;
+            if expanded.as_object().is_some_and(is_blank_node_object) {
+                blank_nodes.push(pointer.to_string());
+            }
+            Ok(expanded)
+        }
+
+        scalar => {
+            // Free-floating scalars are dropped
+            if active_property.is_none() || active_property == Some("@graph") {
+                return Ok(Value::Null);
+            }
+
+            let active = match &property_scoped {
+                Some(scoped) => active.process(scoped, loader, true)?,
+                None => active.clone(),
+            };
+
+            expand_value(&active, active_property.unwrap(), scalar)
+        }
+    }
+}
+
+fn expand_object(
+    active: &Context,
+    active_property: Option<&str>,
+    property_scoped: Option<Value>,
+    map: &Map<String, Value>,
+    pointer: &str,
+    blank_nodes: &mut Vec<String>,
+    loader: &ContextLoader,
+) -> Result<Value, JsonLdError> {
+    let mut active = active.clone();
+
+    // Type-scoped contexts do not propagate into nested nodes
+    if let Some(previous) = &active.previous {
+        let is_value = map.keys().any(|k| active.expand_iri(k, true).as_deref() == Some("@value"));
+        let only_id = map.len() == 1 && map.keys().all(|k| active.expand_iri(k, true).as_deref() == Some("@id"));
+        if !is_value && !only_id {
+            active = (**previous).clone();
+        }
+    }
+
+    if let Some(scoped) = &property_scoped {
+        active = active.process(scoped, loader, true)?;
+    }
+
+    if let Some(local) = map.get("@context") {
+        active = active.process(local, loader, true)?;
+    }
+
+    // Apply the scoped contexts of the node types, in lexicographical order
+    let type_scoped_context = active.clone();
+    let mut type_keys: Vec<&String> = map.keys()
+        .filter(|k| active.expand_iri(k, true).as_deref() == Some("@type"))
+        .collect();
+    type_keys.sort();
+
+    for key in type_keys {
+        let mut types: Vec<&str> = match &map[key] {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        types.sort();
+
+        for t in types {
+            if let Some(scoped) = type_scoped_context.terms.get(t).and_then(|d| d.context.clone()) {
+                active = active.process(&scoped, loader, false)?;
+            }
+        }
+    }
+
+    let mut result = Map::new();
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+
+    for key in keys {
+        if key == "@context" {
+            continue;
+        }
+
+        let value = &map[key];
+        let expanded_property = match active.expand_iri(key, true) {
+            Some(p) if p.contains(':') || is_keyword(&p) => p,
+            None => continue,
+            Some(_) if key.starts_with('@') => continue,
+            Some(_) => {
+                return Err(JsonLdError::InvalidDocument(format!("'{}' does not map to an absolute IRI", key)));
+            }
+        };
+
+        if is_keyword(&expanded_property) {
+            let expanded_value = match expanded_property.as_str() {
+                "@id" => match value {
+                    Value::String(id) => Value::String(active.expand_iri(id, false).unwrap_or_default()),
+                    other => return Err(JsonLdError::InvalidDocument(format!("invalid @id {}", other))),
+                },
+                "@type" => {
+                    let expand_type = |t: &Value| match t {
+                        Value::String(t) => Ok(Value::String(type_scoped_context.expand_iri(t, true).unwrap_or_default())),
+                        other => Err(JsonLdError::InvalidDocument(format!("invalid @type {}", other))),
+                    };
+                    match value {
+                        Value::Array(items) => Value::Array(items.iter().map(expand_type).collect::<Result<_, _>>()?),
+                        other => Value::Array(vec![expand_type(other)?]),
+                    }
+                }
+                "@value" => match value {
+                    Value::Array(_) | Value::Object(_) if !is_json_type(map, &active) => {
+                        return Err(JsonLdError::InvalidDocument(format!("invalid @value {}", value)));
+                    }
+                    other => other.clone(),
+                },
+                "@language" => match value {
+                    Value::String(_) => value.clone(),
+                    other => return Err(JsonLdError::InvalidDocument(format!("invalid @language {}", other))),
+                },
+                "@index" | "@direction" => value.clone(),
+                "@list" => {
+                    if active_property.is_none() || active_property == Some("@graph") {
+                        continue;
+                    }
+                    let list_pointer = format!("{}/{}", pointer, escape_pointer(key));
+                    let expanded = expand_element(&active, active_property, value, &list_pointer, blank_nodes, loader)?;
+                    Value::Array(as_array(expanded))
+                }
+                "@set" => {
+                    let set_pointer = format!("{}/{}", pointer, escape_pointer(key));
+                    expand_element(&active, active_property, value, &set_pointer, blank_nodes, loader)?
+                }
+                "@graph" if active_property.is_none() => {
+                    let graph_pointer = format!("{}/{}", pointer, escape_pointer(key));
+                    let expanded = expand_element(&active, Some("@graph"), value, &graph_pointer, blank_nodes, loader)?;
+                    Value::Array(as_array(expanded))
+                }
+                other => {
+                    return Err(JsonLdError::InvalidDocument(format!("{} is not supported", other)));
+                }
+            };
+
+            result.insert(expanded_property, expanded_value);
+            continue;
+        }
+
+        let definition = active.terms.get(key.as_str());
+        let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+
+        if definition.is_some_and(|d| d.has_container("@index") || d.has_container("@graph")) {
+            return Err(JsonLdError::InvalidDocument(format!("the container of '{}' is not supported", key)));
+        }
+
+        let expanded_value = if type_mapping == Some("@json") {
+            serde_json::json!({ "@value": value, "@type": "@json" })
+        } else if definition.is_some_and(|d| d.has_container("@language")) && value.is_object() {
+            expand_language_map(value.as_object().unwrap())?
+        } else {
+            let value_pointer = format!("{}/{}", pointer, escape_pointer(key));
+            expand_element(&active, Some(key), value, &value_pointer, blank_nodes, loader)?
+        };
+
+        if expanded_value.is_null() {
+            continue;
+        }
+
+        let is_list_object = expanded_value.get("@list").is_some();
+        let expanded_value = if definition.is_some_and(|d| d.has_container("@list")) && !is_list_object {
+            serde_json::json!({ "@list": as_array(expanded_value) })
+        } else {
+            expanded_value
+        };
+
+        let entry = result.entry(expanded_property).or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(values) = entry {
+            values.extend(as_array(expanded_value));
+        }
+    }
+
+    // Value objects
+    if let Some(value) = result.get("@value") {
+        if value.is_null() {
+            return Ok(Value::Null);
+        }
+        if let Some(Value::Array(types)) = result.get("@type") {
+            let single = types.first().cloned().unwrap_or(Value::Null);
+            result.insert("@type".into(), single);
+        }
+        return Ok(Value::Object(result));
+    }
+
+    // @set objects are replaced by their content
+    if let Some(set) = result.remove("@set") {
+        return Ok(set);
+    }
+
+    if result.contains_key("@list") {
+        return Ok(Value::Object(result));
+    }
+
+    if result.len() == 1 && result.contains_key("@language") {
+        return Ok(Value::Null);
+    }
+
+    // A @graph next to other members would be a named graph
+    if result.contains_key("@graph") && result.len() > 1 {
+        return Err(JsonLdError::InvalidDocument("named graphs are not supported".into()));
+    }
+
+    // Drop free-floating values at the top level
+    if active_property.is_none() && (result.is_empty() || (result.len() == 1 && result.contains_key("@id"))) {
+        return Ok(Value::Null);
+    }
+
+    Ok(Value::Object(result))
+}
+
+/// Value Expansion of a scalar in the context of `active_property`.
+fn expand_value(active: &Context, active_property: &str, value: &Value) -> Result<Value, JsonLdError> {
+    let definition = active.terms.get(active_property);
+    let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+
+    match (type_mapping, value) {
+        (Some("@id"), Value::String(id)) => {
+            return Ok(serde_json::json!({ "@id": active.expand_iri(id, false).unwrap_or_default() }));
+        }
+        (Some("@vocab"), Value::String(id)) => {
+            return Ok(serde_json::json!({ "@id": active.expand_iri(id, true).unwrap_or_default() }));
+        }
+        (Some(datatype), _) if !matches!(datatype, "@id" | "@vocab" | "@none") => {
+            return Ok(serde_json::json!({ "@value": value, "@type": datatype }));
+        }
+        _ => {}
+    }
+
+    if let Value::String(_) = value {
+        let language = match definition.and_then(|d| d.language.clone()) {
+            Some(language) => language,
+            None => active.default_language.clone(),
+        };
+
+        if let Some(language) = language {
+            return Ok(serde_json::json!({ "@value": value, "@language": language }));
+        }
+    }
+
+    Ok(serde_json::json!({ "@value": value }))
+}
+
+fn expand_language_map(map: &Map<String, Value>) -> Result<Value, JsonLdError> {
+    let mut result = Vec::new();
+    let mut languages: Vec<&String> = map.keys().collect();
+    languages.sort();
+
+    for language in languages {
+        let values = match &map[language] {
+            Value::Array(items) => items.clone(),
+            other => vec![other.clone()],
+        };
+
+        for value in values {
+            match value {
+                Value::Null => {}
+                Value::String(_) if language == "@none" => {
+                    result.push(serde_json::json!({ "@value": value }));
+                }
+                Value::String(_) => {
+                    result.push(serde_json::json!({ "@value": value, "@language": language }));
+                }
+                other => {
+                    return Err(JsonLdError::InvalidDocument(format!("invalid language map value {}", other)));
+                }
+            }
+        }
+    }
+
+    Ok(Value::Array(result))
+}
+
+fn is_json_type(map: &Map<String, Value>, active: &Context) -> bool {
+    map.iter().any(|(k, v)| {
+        active.expand_iri(k, true).as_deref() == Some("@type") && v.as_str() == Some("@json")
+    })
+}
+
+fn is_blank_node_object(map: &Map<String, Value>) -> bool {
+    !map.contains_key("@id")
+        && !map.contains_key("@value")
+        && !map.contains_key("@list")
+        && !map.contains_key("@set")
+}
+
+/// Escapes a member name as a JSON pointer reference token (RFC 6901).
+pub fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn as_array(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        other => vec![other],
+    }
+}
@@ -0,0 +1,46 @@
+//! Minimal JSON-LD processing needed by the RDF-based cryptosuites: context
+//! processing, expansion, deserialization to RDF, RDF Dataset
+//! Canonicalization (RDFC-1.0) and the selections used by selective disclosure.
+
+pub mod context;
+pub mod expand;
+pub mod rdf;
+pub mod rdfc;
+pub mod select;
+
+use serde_json::Value;
+use std::fmt;
+use crate::jsonld::context::ContextLoader;
+
+#[derive(Debug)]
+pub enum JsonLdError {
+    /// A context could not be processed.
+    InvalidContext(String),
+    /// A remote context is not available in the local cache.
+    UnknownContext(String),
+    /// The document uses JSON-LD features the signer does not support.
+    InvalidDocument(String),
+    /// Canonicalization exceeded its work limit.
+    TooComplex,
+}
+
+impl fmt::Display for JsonLdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonLdError::InvalidContext(msg) => write!(f, "invalid JSON-LD context: {}", msg),
+            JsonLdError::UnknownContext(url) => write!(f, "JSON-LD context '{}' is not available locally", url),
+            JsonLdError::InvalidDocument(msg) => write!(f, "invalid JSON-LD document: {}", msg),
+            JsonLdError::TooComplex => write!(f, "document exceeds the canonicalization work limit"),
+        }
+    }
+}
+
+impl std::error::Error for JsonLdError {}
+
+/// Expands `document`, converts it to RDF and returns its canonical N-Quads.
+pub fn canonize(document: &Value, loader: &ContextLoader) -> Result<String, JsonLdError> {
+    let expanded = expand::expand(document, loader)?;
+    let dataset = rdf::to_rdf(&expanded)?;
+
+    rdfc::canonicalize(&dataset)
+}
@@ -0,0 +1,306 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::jsonld::JsonLdError;
+
+pub const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDF_FIRST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#first";
+const RDF_REST: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#rest";
+const RDF_NIL: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#nil";
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+const RDF_LANG_STRING: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Term {
+    Iri(String),
+    Blank(String),
+    Literal { value: String, datatype: String, language: Option<String> },
+}
+
+impl Term {
+    pub fn blank_id(&self) -> Option<&str> {
+        match self {
+            Term::Blank(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// A triple of the default graph; named graphs are not produced.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Quad {
+    pub subject: Term,
+    pub predicate: Term,
+    pub object: Term,
+}
+
+impl Quad {
+    /// Serializes the quad as a canonical N-Quads line, including the final newline.
+    pub fn to_nquad(&self) -> String {
+        let mut line = String::new();
+        for term in [&self.subject, &self.predicate, &self.object] {
+            write_term(&mut line, term);
+            line.push(' ');
+        }
+        line.push_str(".\n");
+        line
+    }
+}
+
+fn write_term(out: &mut String, term: &Term) {
+    match term {
+        Term::Iri(iri) => {
+            let _ = write!(out, "<{}>", iri);
+        }
+        Term::Blank(id) => {
+            let _ = write!(out, "_:{}", id);
+        }
+        Term::Literal { value, datatype, language } => {
+            out.push('"');
+            escape_literal(out, value);
+            out.push('"');
+            match language {
+                Some(language) => {
+                    let _ = write!(out, "@{}", language);
+                }
+                None if datatype != XSD_STRING => {
+                    let _ = write!(out, "^^<{}>", datatype);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Escapes a literal following the canonical N-Triples rules.
+fn escape_literal(out: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c <= '\u{1f}' || c == '\u{7f}' => {
+                let _ = write!(out, "\\u{:04X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+/// Deserializes an expanded JSON-LD document to RDF (default graph only).
+///
+/// A subject, predicate, type or datatype that is not an absolute IRI fails
+/// the conversion instead of dropping its triple, which would leave it
+/// unsigned. Duplicate triples are dropped.
+pub fn to_rdf(expanded: &[Value]) -> Result<Vec<Quad>, JsonLdError> {
+    let mut converter = Converter::default();
+
+    for node in expanded {
+        if let Value::Object(map) = node {
+            converter.node(map)?;
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    converter.quads.retain(|q| seen.insert(q.clone()));
+    Ok(converter.quads)
+}
+
+#[derive(Default)]
+struct Converter {
+    quads: Vec<Quad>,
+    blank_labels: HashMap<String, String>,
+    counter: usize,
+    /// Number of lists already emitted per subject and property.
+    lists: HashMap<String, usize>,
+}
+
+impl Converter {
+    fn new_blank(&mut self) -> Term {
+        let id = format!("b{}", self.counter);
+        self.counter += 1;
+        Term::Blank(id)
+    }
+
+    fn blank(&mut self, label: &str) -> Term {
+        if let Some(id) = self.blank_labels.get(label) {
+            return Term::Blank(id.clone());
+        }
+
+        let term = self.new_blank();
+        if let Term::Blank(id) = &term {
+            self.blank_labels.insert(label.to_string(), id.clone());
+        }
+        term
+    }
+
+    fn iri_or_blank(&mut self, value: &str) -> Result<Term, JsonLdError> {
+        if value.starts_with("_:") {
+            Ok(self.blank(value))
+        } else if value.contains(':') {
+            Ok(Term::Iri(value.to_string()))
+        } else {
+            Err(not_absolute(value))
+        }
+    }
+
+    /// Emits the triples of a node object and returns its subject.
+    fn node(&mut self, node: &Map<String, Value>) -> Result<Term, JsonLdError> {
+        let subject = match node.get("@id").and_then(Value::as_str) {
+            Some(id) => self.iri_or_blank(id)?,
+            None => self.new_blank(),
+        };
+
+        if let Some(Value::Array(types)) = node.get("@type") {
+            for t in types.iter().filter_map(Value::as_str) {
+                let object = self.iri_or_blank(t)?;
+                self.push(subject.clone(), Term::Iri(RDF_TYPE.into()), object);
+            }
+        }
+
+        for (property, values) in node {
+            if property.starts_with('@') {
+                continue;
+            }
+            if property.starts_with("_:") || !property.contains(':') {
+                return Err(not_absolute(property));
+            }
+
+            for item in values.as_array().into_iter().flatten() {
+                let object = self.object(item, &subject, property)?;
+                self.push(subject.clone(), Term::Iri(property.clone()), object);
+            }
+        }
+
+        Ok(subject)
+    }
+
+    fn object(&mut self, item: &Value, subject: &Term, property: &str) -> Result<Term, JsonLdError> {
+        let Some(map) = item.as_object() else {
+            return Err(JsonLdError::InvalidDocument(format!("{} is not an expanded object", item)));
+        };
+
+        if map.contains_key("@value") {
+            return literal(map);
+        }
+
+        if let Some(Value::Array(list)) = map.get("@list") {
+            return self.list(list, subject, property);
+        }
+
+        self.node(map)
+    }
+
+    /// Emits the nodes of a list held by `property` of `subject`.
+    ///
+    /// List nodes are labelled after their position from the subject, so the
+    /// same list gets the same labels in every document that contains it.
+    fn list(&mut self, items: &[Value], subject: &Term, property: &str) -> Result<Term, JsonLdError> {
+        let owner = match subject {
+            Term::Blank(id) => format!("_:{}", id),
+            Term::Iri(iri) => iri.clone(),
+            Term::Literal { value, .. } => value.clone(),
+        };
+        let occurrence = self.lists.entry(format!("{} {}", owner, property)).or_default();
+        let label = format!("{} {} {}", owner, property, occurrence);
+        *occurrence += 1;
+
+        let mut nodes = Vec::new();
+        for item in items {
+            let node = Term::Blank(format!("{} {}", label, nodes.len()));
+            let object = self.object(item, &node, RDF_FIRST)?;
+            nodes.push((node, object));
+        }
+
+        for (i, (node, object)) in nodes.iter().enumerate() {
+            let rest = nodes.get(i + 1).map(|(next, _)| next.clone()).unwrap_or(Term::Iri(RDF_NIL.into()));
+            self.push(node.clone(), Term::Iri(RDF_FIRST.into()), object.clone());
+            self.push(node.clone(), Term::Iri(RDF_REST.into()), rest);
+        }
+
+        Ok(nodes.first().map(|(node, _)| node.clone()).unwrap_or(Term::Iri(RDF_NIL.into())))
+    }
+
+    fn push(&mut self, subject: Term, predicate: Term, object: Term) {
+        self.quads.push(Quad { subject, predicate, object });
+    }
+}
+
+fn not_absolute(iri: &str) -> JsonLdError {
+    JsonLdError::InvalidDocument(format!("'{}' is not an absolute IRI", iri))
+}
+
+fn literal(map: &Map<String, Value>) -> Result<Term, JsonLdError> {
+    let value = &map["@value"];
+    let datatype = map.get("@type").and_then(Value::as_str);
+
+    if datatype == Some("@json") {
+        return Ok(Term::Literal {
+            value: serde_json::to_string(value).map_err(|e| JsonLdError::InvalidDocument(e.to_string()))?,
+            datatype: RDF_JSON.into(),
+            language: None,
+        });
+    }
+
+    // Relative datatype IRIs cannot be represented
+    if let Some(datatype) = datatype.filter(|d| !d.contains(':')) {
+        return Err(not_absolute(datatype));
+    }
+
+    let (lexical, default_datatype) = match value {
+        Value::Bool(b) => (b.to_string(), XSD_BOOLEAN),
+        Value::Number(n) => {
+            let f = n.as_f64().unwrap_or_default();
+            let is_integer = n.is_i64() || n.is_u64() || (f.fract() == 0.0 && f.abs() < 1e21);
+
+            if is_integer && datatype != Some(XSD_DOUBLE) {
+                let lexical = match (n.as_i64(), n.as_u64()) {
+                    (Some(i), _) => i.to_string(),
+                    (_, Some(u)) => u.to_string(),
+                    _ => format!("{:.0}", f),
+                };
+                (lexical, XSD_INTEGER)
+            } else {
+                (canonical_double(f), XSD_DOUBLE)
+            }
+        }
+        Value::String(s) => {
+            if let Some(language) = map.get("@language").and_then(Value::as_str) {
+                return Ok(Term::Literal {
+                    value: s.clone(),
+                    datatype: RDF_LANG_STRING.into(),
+                    language: Some(language.to_string()),
+                });
+            }
+            (s.clone(), XSD_STRING)
+        }
+        other => return Err(JsonLdError::InvalidDocument(format!("invalid @value {}", other))),
+    };
+
+    Ok(Term::Literal {
+        value: lexical,
+        datatype: datatype.unwrap_or(default_datatype).to_string(),
+        language: None,
+    })
+}
+
+/// Canonical `xsd:double` lexical form, e.g. `21.5` → `2.15E1`.
+fn canonical_double(value: f64) -> String {
+    let formatted = format!("{:.15E}", value);
+    let (mantissa, exponent) = formatted.split_once('E').unwrap_or((&formatted, "0"));
+
+    let mut mantissa = mantissa.trim_end_matches('0').to_string();
+    if mantissa.ends_with('.') {
+        mantissa.push('0');
+    }
+
+    format!("{}E{}", mantissa, exponent)
+}
@@ -0,0 +1,315 @@
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use crate::jsonld::rdf::{Quad, Term};
+use crate::jsonld::JsonLdError;
+
+/// Upper bound on the n-degree hashing work, to protect the service against
+/// poison graphs crafted to make canonicalization explode.
+const MAX_WORK: usize = 10_000;
+
+/// Largest group of equivalent blank nodes whose permutations are explored.
+const MAX_PERMUTED: usize = 6;
+
+/// RDF Dataset Canonicalization (RDFC-1.0) with SHA-256.
+///
+/// Returns the canonical N-Quads document: blank nodes relabelled `_:c14nN`,
+/// lines sorted in code point order.
+pub fn canonicalize(quads: &[Quad]) -> Result<String, JsonLdError> {
+    let labels = canonical_labels(quads)?;
+    Ok(relabel(quads, &labels).concat())
+}
+
+/// Issues the canonical labels of the blank nodes of `quads`, as a map from
+/// their input label to `c14nN`.
+pub fn canonical_labels(quads: &[Quad]) -> Result<HashMap<String, String>, JsonLdError> {
+    let mut state = CanonicalizationState::new(quads);
+
+    // Blank nodes with a unique first-degree hash are labelled first
+    let mut hash_to_blanks: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut blanks: Vec<String> = state.blank_to_quads.keys().cloned().collect();
+    blanks.sort();
+    for blank in blanks {
+        hash_to_blanks.entry(state.hash_first_degree(&blank)).or_default().push(blank);
+    }
+
+    let mut shared = Vec::new();
+    for (_, blanks) in hash_to_blanks {
+        if blanks.len() == 1 {
+            state.canonical.issue(&blanks[0]);
+        } else {
+            shared.push(blanks);
+        }
+    }
+
+    // The others are told apart by hashing their surroundings
+    for blanks in shared {
+        let mut results = Vec::new();
+        for blank in &blanks {
+            if state.canonical.has(blank) {
+                continue;
+            }
+
+            let mut issuer = IdentifierIssuer::new("b");
+            issuer.issue(blank);
+            results.push(state.hash_n_degree(blank, issuer)?);
+        }
+
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        for (_, issuer) in results {
+            for (existing, _) in &issuer.issued {
+                state.canonical.issue(existing);
+            }
+        }
+    }
+
+    Ok(state.canonical.issued.into_iter().collect())
+}
+
+/// Serializes `quads` as sorted, deduplicated N-Quads lines, with blank
+/// nodes renamed according to `labels`.
+pub fn relabel(quads: &[Quad], labels: &HashMap<String, String>) -> Vec<String> {
+    let mut lines: Vec<String> = quads.iter()
+        .map(|quad| {
+            let relabel = |term: &Term| match term {
+                Term::Blank(id) => Term::Blank(labels.get(id).unwrap_or(id).to_string()),
+                other => other.clone(),
+            };
+            Quad {
+                subject: relabel(&quad.subject),
+                predicate: quad.predicate.clone(),
+                object: relabel(&quad.object),
+            }
+            .to_nquad()
+        })
+        .collect();
+
+    lines.sort();
+    lines.dedup();
+    lines
+}
+
+#[derive(Clone)]
+struct IdentifierIssuer {
+    prefix: &'static str,
+    /// Issued identifiers, in issuance order.
+    issued: Vec<(String, String)>,
+    index: HashMap<String, usize>,
+}
+
+impl IdentifierIssuer {
+    fn new(prefix: &'static str) -> Self {
+        IdentifierIssuer { prefix, issued: Vec::new(), index: HashMap::new() }
+    }
+
+    fn issue(&mut self, existing: &str) -> String {
+        if let Some(i) = self.index.get(existing) {
+            return self.issued[*i].1.clone();
+        }
+
+        let id = format!("{}{}", self.prefix, self.issued.len());
+        self.index.insert(existing.to_string(), self.issued.len());
+        self.issued.push((existing.to_string(), id.clone()));
+        id
+    }
+
+    fn has(&self, existing: &str) -> bool {
+        self.index.contains_key(existing)
+    }
+
+    fn get(&self, existing: &str) -> Option<&str> {
+        self.index.get(existing).map(|i| self.issued[*i].1.as_str())
+    }
+}
+
+struct CanonicalizationState<'a> {
+    blank_to_quads: HashMap<String, Vec<&'a Quad>>,
+    canonical: IdentifierIssuer,
+    first_degree: HashMap<String, String>,
+    work: usize,
+}
+
+impl<'a> CanonicalizationState<'a> {
+    fn new(quads: &'a [Quad]) -> Self {
+        let mut blank_to_quads: HashMap<String, Vec<&Quad>> = HashMap::new();
+        for quad in quads {
+            for id in [&quad.subject, &quad.object].into_iter().filter_map(Term::blank_id) {
+                let entry = blank_to_quads.entry(id.to_string()).or_default();
+                if !entry.iter().any(|q| std::ptr::eq(*q, quad)) {
+                    entry.push(quad);
+                }
+            }
+        }
+
+        CanonicalizationState {
+            blank_to_quads,
+            canonical: IdentifierIssuer::new("c14n"),
+            first_degree: HashMap::new(),
+            work: 0,
+        }
+    }
+
+    /// Hash First Degree Quads.
+    fn hash_first_degree(&mut self, reference: &str) -> String {
+        if let Some(hash) = self.first_degree.get(reference) {
+            return hash.clone();
+        }
+
+        let relabel = |term: &Term| match term {
+            Term::Blank(id) if id == reference => Term::Blank("a".into()),
+            Term::Blank(_) => Term::Blank("z".into()),
+            other => other.clone(),
+        };
+
+        let mut lines: Vec<String> = self.blank_to_quads[reference].iter()
+            .map(|quad| Quad {
+                subject: relabel(&quad.subject),
+                predicate: quad.predicate.clone(),
+                object: relabel(&quad.object),
+            }.to_nquad())
+            .collect();
+        lines.sort();
+
+        let hash = sha256_hex(lines.concat().as_bytes());
+        self.first_degree.insert(reference.to_string(), hash.clone());
+        hash
+    }
+
+    /// Hash Related Blank Node.
+    fn hash_related(&mut self, related: &str, quad: &Quad, issuer: &IdentifierIssuer, position: char) -> String {
+        let identifier = match self.canonical.get(related).or_else(|| issuer.get(related)) {
+            Some(id) => format!("_:{}", id),
+            None => self.hash_first_degree(related),
+        };
+
+        let predicate = match &quad.predicate {
+            Term::Iri(iri) => format!("<{}>", iri),
+            _ => String::new(),
+        };
+
+        sha256_hex(format!("{}{}{}", position, predicate, identifier).as_bytes())
+    }
+
+    /// Hash N-Degree Quads; returns the hash and the issuer it settled on.
+    fn hash_n_degree(
+        &mut self,
+        identifier: &str,
+        mut issuer: IdentifierIssuer,
+    ) -> Result<(String, IdentifierIssuer), JsonLdError> {
+        self.tick()?;
+
+        let mut related_hashes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let quads = self.blank_to_quads[identifier].clone();
+        for quad in quads {
+            for (term, position) in [(&quad.subject, 's'), (&quad.object, 'o')] {
+                if let Some(related) = term.blank_id().filter(|id| *id != identifier) {
+                    let hash = self.hash_related(related, quad, &issuer, position);
+                    related_hashes.entry(hash).or_default().push(related.to_string());
+                }
+            }
+        }
+
+        let mut data_to_hash = String::new();
+
+        for (related_hash, blanks) in related_hashes {
+            data_to_hash.push_str(&related_hash);
+
+            if blanks.len() > MAX_PERMUTED {
+                return Err(JsonLdError::TooComplex);
+            }
+
+            let mut chosen_path = String::new();
+            let mut chosen_issuer = None;
+
+            for permutation in permutations(&blanks) {
+                self.tick()?;
+
+                let mut issuer_copy = issuer.clone();
+                let mut path = String::new();
+                let mut recursion_list = Vec::new();
+                let mut skip = false;
+
+                for related in &permutation {
+                    match self.canonical.get(related) {
+                        Some(id) => path.push_str(&format!("_:{}", id)),
+                        None => {
+                            if !issuer_copy.has(related) {
+                                recursion_list.push(related.clone());
+                            }
+                            path.push_str(&format!("_:{}", issuer_copy.issue(related)));
+                        }
+                    }
+
+                    if worse_path(&path, &chosen_path) {
+                        skip = true;
+                        break;
+                    }
+                }
+
+                if skip {
+                    continue;
+                }
+
+                for related in recursion_list {
+                    let (hash, result_issuer) = self.hash_n_degree(&related, issuer_copy.clone())?;
+                    path.push_str(&format!("_:{}", issuer_copy.issue(&related)));
+                    path.push_str(&format!("<{}>", hash));
+                    issuer_copy = result_issuer;
+
+                    if worse_path(&path, &chosen_path) {
+                        skip = true;
+                        break;
+                    }
+                }
+
+                if skip {
+                    continue;
+                }
+
+                if chosen_issuer.is_none() || path < chosen_path {
+                    chosen_path = path;
+                    chosen_issuer = Some(issuer_copy);
+                }
+            }
+
+            data_to_hash.push_str(&chosen_path);
+            if let Some(chosen) = chosen_issuer {
+                issuer = chosen;
+            }
+        }
+
+        Ok((sha256_hex(data_to_hash.as_bytes()), issuer))
+    }
+
+    fn tick(&mut self) -> Result<(), JsonLdError> {
+        self.work += 1;
+        if self.work > MAX_WORK {
+            return Err(JsonLdError::TooComplex);
+        }
+        Ok(())
+    }
+}
+
+fn worse_path(path: &str, chosen_path: &str) -> bool {
+    !chosen_path.is_empty() && path.len() >= chosen_path.len() && path > chosen_path
+}
+
+fn permutations(items: &[String]) -> Vec<Vec<String>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+
+    let mut result = Vec::new();
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(i);
+        for mut permutation in permutations(&rest) {
+            permutation.insert(0, first.clone());
+            result.push(permutation);
+        }
+    }
+    result
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
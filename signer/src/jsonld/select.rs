@@ -0,0 +1,154 @@
+//! Building blocks of selective disclosure: blank node skolemization and
+//! selection of the parts of a compact document named by JSON pointers.
+
+use serde_json::{Map, Value};
+use crate::jsonld::context::ContextLoader;
+use crate::jsonld::rdf::{self, Quad, Term};
+use crate::jsonld::{expand, JsonLdError};
+
+/// Prefix of the IRIs temporarily given to blank nodes.
+pub const SKOLEM_PREFIX: &str = "urn:bnid:";
+
+/// Gives every node object without an `@id` a `urn:bnid:` identifier, so that
+/// its blank node keeps the same label in every selection of the document.
+pub fn skolemize(document: &Value, loader: &ContextLoader) -> Result<Value, JsonLdError> {
+    let mut skolemized = document.clone();
+
+    for (i, pointer) in expand::blank_node_pointers(document, loader)?.iter().enumerate() {
+        if let Some(Value::Object(node)) = skolemized.pointer_mut(pointer) {
+            node.insert("@id".into(), Value::String(format!("{}s{}", SKOLEM_PREFIX, i)));
+        }
+    }
+
+    Ok(skolemized)
+}
+
+/// Converts a skolemized document to RDF, turning `urn:bnid:` IRIs back into
+/// blank nodes labelled with their skolem identifier.
+pub fn deskolemized_quads(document: &Value, loader: &ContextLoader) -> Result<Vec<Quad>, JsonLdError> {
+    let expanded = expand::expand(document, loader)?;
+    let deskolemize = |term: Term| match term {
+        Term::Iri(iri) if iri.starts_with(SKOLEM_PREFIX) => Term::Blank(iri[SKOLEM_PREFIX.len()..].to_string()),
+        other => other,
+    };
+
+    Ok(rdf::to_rdf(&expanded)?
+        .into_iter()
+        .map(|quad| Quad {
+            subject: deskolemize(quad.subject),
+            predicate: quad.predicate,
+            object: deskolemize(quad.object),
+        })
+        .collect())
+}
+
+/// Removes the `urn:bnid:` identifiers added by [`skolemize`] and the holes
+/// left in arrays by [`select`].
+pub fn unskolemize(document: &Value) -> Value {
+    match document {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(k, v)| {
+                    !(k.as_str() == "@id" && v.as_str().is_some_and(|id| id.starts_with(SKOLEM_PREFIX)))
+                })
+                .map(|(k, v)| (k.clone(), unskolemize(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().filter(|v| !v.is_null()).map(unskolemize).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Selects the values named by `pointers` from a compact document.
+///
+/// The selection keeps the `@context` of the document and, for every object
+/// on the way to a selected value, its identifier and type, so that the
+/// selected values stay attached to the same subjects. Array elements keep
+/// their position; unselected elements are left as `null`.
+pub fn select(document: &Value, pointers: &[String]) -> Result<Value, JsonLdError> {
+    let root = document.as_object()
+        .ok_or_else(|| JsonLdError::InvalidDocument("only objects can be selected from".into()))?;
+
+    let mut selection = initial_selection(root);
+    if let Some(context) = root.get("@context") {
+        selection.insert("@context".into(), context.clone());
+    }
+    let mut selection = Value::Object(selection);
+
+    for pointer in pointers {
+        let tokens = parse_pointer(pointer)?;
+        if tokens.is_empty() {
+            selection = document.clone();
+            continue;
+        }
+
+        let mut value = document;
+        let mut selected = &mut selection;
+
+        for (depth, token) in tokens.iter().enumerate() {
+            let unknown = || JsonLdError::InvalidDocument(format!("JSON pointer '{}' does not match the document", pointer));
+            let last = depth + 1 == tokens.len();
+
+            value = match value {
+                Value::Object(map) => map.get(token.as_str()).ok_or_else(unknown)?,
+                Value::Array(items) => token.parse::<usize>().ok()
+                    .and_then(|i| items.get(i))
+                    .ok_or_else(unknown)?,
+                _ => return Err(unknown()),
+            };
+
+            let initial = match value {
+                _ if last => value.clone(),
+                Value::Object(map) => Value::Object(initial_selection(map)),
+                Value::Array(_) => Value::Array(Vec::new()),
+                other => other.clone(),
+            };
+
+            selected = match selected {
+                Value::Object(map) => {
+                    let entry = map.entry(token.clone()).or_insert(Value::Null);
+                    if last || entry.is_null() {
+                        *entry = initial;
+                    }
+                    entry
+                }
+                Value::Array(items) => {
+                    let i: usize = token.parse().map_err(|_| unknown())?;
+                    if items.len() <= i {
+                        items.resize(i + 1, Value::Null);
+                    }
+                    if last || items[i].is_null() {
+                        items[i] = initial;
+                    }
+                    &mut items[i]
+                }
+                _ => return Err(unknown()),
+            };
+        }
+    }
+
+    Ok(selection)
+}
+
+/// Identifier and type of an object, which every selection inside it carries.
+fn initial_selection(map: &Map<String, Value>) -> Map<String, Value> {
+    map.iter()
+        .filter(|(k, v)| {
+            let is_blank_id = v.as_str().is_some_and(|id| id.starts_with("_:"));
+            matches!(k.as_str(), "id" | "@id" | "type" | "@type") && !is_blank_id
+        })
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+/// Splits a JSON pointer (RFC 6901) into its unescaped reference tokens.
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, JsonLdError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rest = pointer.strip_prefix('/')
+        .ok_or_else(|| JsonLdError::InvalidDocument(format!("invalid JSON pointer '{}'", pointer)))?;
+
+    Ok(rest.split('/').map(|token| token.replace("~1", "/").replace("~0", "~")).collect())
+}
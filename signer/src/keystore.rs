@@ -0,0 +1,504 @@
+use serde::{Deserialize, Serialize};
+use ed25519_dalek::SecretKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+use tracing::{info};
+use crate::bbs;
+use crate::resolver::PublicKey;
+
+/// Default location of the key store file, relative to the working directory.
+pub const DEFAULT_KEY_STORE_PATH: &str = "keys/keystore.json";
+
+/// Environment variable used to override the key store location.
+pub const KEY_STORE_PATH_ENV: &str = "KEY_STORE_PATH";
+
+/// Default number of days a retired key remains usable for verification.
+pub const DEFAULT_KEY_RETENTION_DAYS: i64 = 365;
+
+/// Environment variable used to override the retired key retention period.
+pub const KEY_RETENTION_DAYS_ENV: &str = "KEY_RETENTION_DAYS";
+
+#[derive(Debug)]
+pub enum KeyStoreError {
+    Io(std::io::Error),
+    Format(String),
+    UnknownKey(String),
+    InvalidTransition(String),
+}
+
+impl fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStoreError::Io(e) => write!(f, "key store I/O error: {}", e),
+            KeyStoreError::Format(msg) => write!(f, "invalid key store: {}", msg),
+            KeyStoreError::UnknownKey(id) => write!(f, "unknown key '{}'", id),
+            KeyStoreError::InvalidTransition(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for KeyStoreError {}
+
+impl From<std::io::Error> for KeyStoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeyStoreError::Io(e)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, Default, PartialEq)]
+pub enum KeyAlgorithm {
+    #[default]
+    Ed25519,
+    #[serde(rename = "P-256")]
+    P256,
+    #[serde(rename = "P-384")]
+    P384,
+    /// BLS12-381 G2 keys, for BBS signatures.
+    Bls12381G2,
+    /// ML-DSA-65 (FIPS 204) post-quantum keys.
+    #[serde(rename = "ML-DSA-65")]
+    MlDsa65,
+}
+
+impl KeyAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyAlgorithm::Ed25519 => "Ed25519",
+            KeyAlgorithm::P256 => "P-256",
+            KeyAlgorithm::P384 => "P-384",
+            KeyAlgorithm::Bls12381G2 => "Bls12381G2",
+            KeyAlgorithm::MlDsa65 => "ML-DSA-65",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Ed25519" => Some(KeyAlgorithm::Ed25519),
+            "P-256" => Some(KeyAlgorithm::P256),
+            "P-384" => Some(KeyAlgorithm::P384),
+            "Bls12381G2" => Some(KeyAlgorithm::Bls12381G2),
+            "ML-DSA-65" => Some(KeyAlgorithm::MlDsa65),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyStatus {
+    /// Created and published, but not used for signing yet.
+    Pending,
+    /// Used for new signatures.
+    Active,
+    /// Only kept to verify proofs created before it was replaced.
+    Retired,
+}
+
+impl KeyStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyStatus::Pending => "pending",
+            KeyStatus::Active => "active",
+            KeyStatus::Retired => "retired",
+        }
+    }
+}
+
+fn default_status() -> KeyStatus {
+    KeyStatus::Active
+}
+
+/// A key as persisted on disk.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct StoredKey {
+    id: String,
+    algorithm: KeyAlgorithm,
+    #[serde(rename = "secretKey")]
+    secret_key: String,
+    created: String,
+    #[serde(default = "default_status")]
+    status: KeyStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retired: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct KeyStoreFile {
+    keys: Vec<StoredKey>,
+}
+
+/// Private key material of a service key.
+#[derive(Clone)]
+enum KeyPair {
+    Ed25519(ed25519_dalek::SigningKey),
+    P256(p256::ecdsa::SigningKey),
+    P384(p384::ecdsa::SigningKey),
+    Bls12381G2(bbs::SecretKey),
+    /// The FIPS 204 seed, expanded into the signing key when needed.
+    MlDsa65([u8; mldsa::SEED_LENGTH]),
+}
+
+impl KeyPair {
+    fn generate(algorithm: KeyAlgorithm) -> Self {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => {
+                let mut secret_bytes = [0u8; 32];
+                OsRng.fill_bytes(&mut secret_bytes);
+                let secret_key = SecretKey::from(secret_bytes);
+                KeyPair::Ed25519(ed25519_dalek::SigningKey::from_bytes(&secret_key))
+            }
+            KeyAlgorithm::P256 => KeyPair::P256(p256::ecdsa::SigningKey::random(&mut OsRng)),
+            KeyAlgorithm::P384 => KeyPair::P384(p384::ecdsa::SigningKey::random(&mut OsRng)),
+            KeyAlgorithm::Bls12381G2 => KeyPair::Bls12381G2(bbs::SecretKey::random()),
+            KeyAlgorithm::MlDsa65 => {
+                let mut seed = [0u8; mldsa::SEED_LENGTH];
+                OsRng.fill_bytes(&mut seed);
+                KeyPair::MlDsa65(seed)
+            }
+        }
+    }
+
+    fn from_bytes(algorithm: KeyAlgorithm, bytes: &[u8]) -> Option<Self> {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => {
+                let secret_key: SecretKey = bytes.try_into().ok()?;
+                Some(KeyPair::Ed25519(ed25519_dalek::SigningKey::from_bytes(&secret_key)))
+            }
+            KeyAlgorithm::P256 => p256::ecdsa::SigningKey::from_slice(bytes).ok().map(KeyPair::P256),
+            KeyAlgorithm::P384 => p384::ecdsa::SigningKey::from_slice(bytes).ok().map(KeyPair::P384),
+            KeyAlgorithm::Bls12381G2 => bbs::SecretKey::from_bytes(bytes).map(KeyPair::Bls12381G2),
+            KeyAlgorithm::MlDsa65 => bytes.try_into().ok().map(KeyPair::MlDsa65),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            KeyPair::Ed25519(key) => key.to_bytes().to_vec(),
+            KeyPair::P256(key) => key.to_bytes().to_vec(),
+            KeyPair::P384(key) => key.to_bytes().to_vec(),
+            KeyPair::Bls12381G2(key) => key.to_bytes().to_vec(),
+            KeyPair::MlDsa65(seed) => seed.to_vec(),
+        }
+    }
+}
+
+/// A decoded key of the service.
+#[derive(Clone)]
+pub struct ServiceKey {
+    pub id: String,
+    pub algorithm: KeyAlgorithm,
+    pub created: String,
+    pub status: KeyStatus,
+    /// When the key stopped being used for signing.
+    pub retired: Option<DateTime<Utc>>,
+    key_pair: KeyPair,
+}
+
+impl ServiceKey {
+    fn generate(id: String, algorithm: KeyAlgorithm, status: KeyStatus) -> Self {
+        ServiceKey {
+            id,
+            algorithm,
+            created: Utc::now().to_rfc3339(),
+            status,
+            retired: None,
+            key_pair: KeyPair::generate(algorithm),
+        }
+    }
+
+    fn from_stored(stored: StoredKey) -> Result<Self, KeyStoreError> {
+        let bytes = STANDARD.decode(&stored.secret_key)
+            .map_err(|e| KeyStoreError::Format(format!("key '{}': {}", stored.id, e)))?;
+
+        let key_pair = KeyPair::from_bytes(stored.algorithm, &bytes)
+            .ok_or_else(|| KeyStoreError::Format(
+                format!("key '{}': invalid {} secret key", stored.id, stored.algorithm.as_str()),
+            ))?;
+
+        let retired = match &stored.retired {
+            Some(ts) => Some(
+                DateTime::parse_from_rfc3339(ts)
+                    .map_err(|e| KeyStoreError::Format(format!("key '{}': {}", stored.id, e)))?
+                    .with_timezone(&Utc),
+            ),
+            None => None,
+        };
+
+        Ok(ServiceKey {
+            id: stored.id,
+            algorithm: stored.algorithm,
+            created: stored.created,
+            status: stored.status,
+            retired,
+            key_pair,
+        })
+    }
+
+    fn to_stored(&self) -> StoredKey {
+        StoredKey {
+            id: self.id.clone(),
+            algorithm: self.algorithm,
+            secret_key: STANDARD.encode(self.key_pair.to_bytes()),
+            created: self.created.clone(),
+            status: self.status,
+            retired: self.retired.map(|ts| ts.to_rfc3339()),
+        }
+    }
+
+    /// Signs `message`; ECDSA signatures are in IEEE P1363 (`r || s`) form,
+    /// BBS signatures sign `message` as their only message, ML-DSA signatures
+    /// use an empty context string. Only BBS signing can fail.
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, bbs::BbsError> {
+        use ed25519_dalek::Signer as _;
+
+        Ok(match &self.key_pair {
+            KeyPair::Ed25519(key) => key.sign(message).to_bytes().to_vec(),
+            KeyPair::P256(key) => {
+                let signature: p256::ecdsa::Signature = key.sign(message);
+                signature.to_bytes().to_vec()
+            }
+            KeyPair::P384(key) => {
+                let signature: p384::ecdsa::Signature = key.sign(message);
+                signature.to_bytes().to_vec()
+            }
+            KeyPair::Bls12381G2(key) => key.sign(&[], &[message.to_vec()])?,
+            KeyPair::MlDsa65(seed) => {
+                // Hedged signing: fresh randomness for every signature
+                let mut rnd = [0u8; mldsa::RND_LENGTH];
+                OsRng.fill_bytes(&mut rnd);
+
+                let (key, _) = mldsa::SigningKeySeed::from(*seed).expand();
+                key.sign(message, &[], &rnd)
+                    .expect("empty context is always valid")
+                    .as_bytes()
+                    .to_vec()
+            }
+        })
+    }
+
+    /// Signs a list of messages bound to `header` with a BBS key; `None` for
+    /// keys of other algorithms.
+    pub fn sign_messages(&self, header: &[u8], messages: &[Vec<u8>]) -> Option<Result<Vec<u8>, bbs::BbsError>> {
+        match &self.key_pair {
+            KeyPair::Bls12381G2(key) => Some(key.sign(header, messages)),
+            _ => None,
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        match &self.key_pair {
+            KeyPair::Ed25519(key) => PublicKey::Ed25519(key.verifying_key()),
+            KeyPair::P256(key) => PublicKey::P256(*key.verifying_key()),
+            KeyPair::P384(key) => PublicKey::P384(*key.verifying_key()),
+            KeyPair::Bls12381G2(key) => PublicKey::Bls12381G2(key.public_key()),
+            KeyPair::MlDsa65(seed) => PublicKey::MlDsa65(Box::new(mldsa::SigningKeySeed::from(*seed).expand().1)),
+        }
+    }
+}
+
+/// Long-lived signing keys of the service, loaded once at startup.
+pub struct KeyStore {
+    path: PathBuf,
+    keys: Vec<ServiceKey>,
+    retention: Duration,
+}
+
+impl KeyStore {
+    /// Loads the key store from `path`, generating and persisting a new
+    /// Ed25519 key if the file does not exist yet.
+    pub fn load_or_generate(path: impl AsRef<Path>) -> Result<Self, KeyStoreError> {
+        let path = path.as_ref().to_path_buf();
+
+        if path.exists() {
+            info!("Loading signing keys from {}", path.display());
+
+            let content = fs::read_to_string(&path)?;
+            let file: KeyStoreFile = serde_json::from_str(&content)
+                .map_err(|e| KeyStoreError::Format(e.to_string()))?;
+
+            let keys = file.keys.into_iter()
+                .map(ServiceKey::from_stored)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if !keys.iter().any(|k| k.status == KeyStatus::Active && k.algorithm == KeyAlgorithm::Ed25519) {
+                return Err(KeyStoreError::Format("no active Ed25519 key found".into()));
+            }
+
+            return Ok(KeyStore { path, keys, retention: Duration::days(DEFAULT_KEY_RETENTION_DAYS) });
+        }
+
+        info!("No key store found at {}, generating a new signing key", path.display());
+
+        let store = KeyStore {
+            path,
+            keys: vec![ServiceKey::generate("key-1".to_string(), KeyAlgorithm::Ed25519, KeyStatus::Active)],
+            retention: Duration::days(DEFAULT_KEY_RETENTION_DAYS),
+        };
+        store.save(&store.keys)?;
+
+        Ok(store)
+    }
+
+    /// Sets how long retired keys remain usable for verification.
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// End of the verification window of a key, if it has been retired.
+    pub fn verifiable_until(&self, key: &ServiceKey) -> Option<DateTime<Utc>> {
+        key.retired.map(|ts| ts + self.retention)
+    }
+
+    /// Keys that may still be used for verification: every key except retired
+    /// ones whose retention period is over.
+    pub fn verifiable_keys(&self) -> impl Iterator<Item = &ServiceKey> {
+        let now = Utc::now();
+        self.keys.iter()
+            .filter(move |k| self.verifiable_until(k).is_none_or(|until| now <= until))
+    }
+
+    /// Looks up a verifiable key by id.
+    pub fn verifiable_key(&self, id: &str) -> Option<&ServiceKey> {
+        self.verifiable_keys().find(|k| k.id == id)
+    }
+
+    /// Creates a new pending key; it is published but only used for signing
+    /// once activated.
+    pub fn create_key(&mut self, algorithm: KeyAlgorithm) -> Result<&ServiceKey, KeyStoreError> {
+        let next = self.keys.iter()
+            .filter_map(|k| k.id.strip_prefix("key-").and_then(|n| n.parse::<u32>().ok()))
+            .max()
+            .unwrap_or(0) + 1;
+
+        // The keys in memory only change once the store is saved
+        let mut keys = self.keys.clone();
+        keys.push(ServiceKey::generate(format!("key-{}", next), algorithm, KeyStatus::Pending));
+        self.save(&keys)?;
+        self.keys = keys;
+
+        info!("Created pending {} key 'key-{}'", algorithm.as_str(), next);
+
+        Ok(self.keys.last().unwrap())
+    }
+
+    /// Promotes a pending key to active, retiring the previously active key of
+    /// the same algorithm.
+    pub fn activate(&mut self, id: &str) -> Result<&ServiceKey, KeyStoreError> {
+        let index = self.keys.iter()
+            .position(|k| k.id == id)
+            .ok_or_else(|| KeyStoreError::UnknownKey(id.to_string()))?;
+
+        match self.keys[index].status {
+            KeyStatus::Active => return Ok(&self.keys[index]),
+            KeyStatus::Retired => {
+                return Err(KeyStoreError::InvalidTransition(
+                    format!("key '{}' is retired and cannot be activated again", id),
+                ));
+            }
+            KeyStatus::Pending => {}
+        }
+
+        let now = Utc::now();
+        let algorithm = self.keys[index].algorithm;
+        let mut keys = self.keys.clone();
+        for key in keys.iter_mut().filter(|k| k.status == KeyStatus::Active && k.algorithm == algorithm) {
+            info!("Retiring key '{}'", key.id);
+            key.status = KeyStatus::Retired;
+            key.retired = Some(now);
+        }
+        keys[index].status = KeyStatus::Active;
+        self.save(&keys)?;
+        self.keys = keys;
+
+        info!("Activated key '{}'", id);
+
+        Ok(&self.keys[index])
+    }
+
+    /// The Ed25519 key currently used for signing.
+    pub fn active_key(&self) -> &ServiceKey {
+        self.active_key_for(KeyAlgorithm::Ed25519)
+            .expect("key store always holds an active Ed25519 key")
+    }
+
+    /// Identifier of the Ed25519 key currently used for signing.
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key().id
+    }
+
+    /// The key currently used for signing with `algorithm`, if any.
+    pub fn active_key_for(&self, algorithm: KeyAlgorithm) -> Option<&ServiceKey> {
+        self.keys.iter()
+            .find(|k| k.status == KeyStatus::Active && k.algorithm == algorithm)
+    }
+
+    /// Returns the active key for `algorithm`, creating and activating one
+    /// the first time the algorithm is needed.
+    pub fn ensure_active(&mut self, algorithm: KeyAlgorithm) -> Result<&ServiceKey, KeyStoreError> {
+        if self.active_key_for(algorithm).is_none() {
+            let id = self.create_key(algorithm)?.id.clone();
+            self.activate(&id)?;
+        }
+
+        Ok(self.active_key_for(algorithm).unwrap())
+    }
+
+    /// All keys in the store, including retired keys past their retention period.
+    pub fn keys(&self) -> &[ServiceKey] {
+        &self.keys
+    }
+
+    /// Writes `keys` as the content of the store.
+    fn save(&self, keys: &[ServiceKey]) -> Result<(), KeyStoreError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = KeyStoreFile { keys: keys.iter().map(ServiceKey::to_stored).collect() };
+        let content = serde_json::to_string_pretty(&file)
+            .map_err(|e| KeyStoreError::Format(e.to_string()))?;
+
+        // Written to a temporary file renamed over the store, so that a crash
+        // never leaves a truncated store behind
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+        if let Err(e) = fs::remove_file(&temp_path) && e.kind() != std::io::ErrorKind::NotFound {
+            return Err(e.into());
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        // The file holds private key material: readable by the owner only,
+        // from its creation on
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut temp_file = options.open(&temp_path)?;
+        temp_file.write_all(content.as_bytes())?;
+        temp_file.sync_all()?;
+        drop(temp_file);
+
+        fs::rename(&temp_path, &self.path)?;
+
+        // Persist the rename itself
+        #[cfg(unix)]
+        if let Some(parent) = self.path.parent() {
+            let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            fs::File::open(parent)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
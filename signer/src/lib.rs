@@ -1,3 +1,14 @@
+pub mod attribute;
+pub mod bbs;
+pub mod bbs2023;
+pub mod cryptosuite;
 pub mod handlers;
+pub mod jcs;
+pub mod jsonld;
+pub mod keystore;
+pub mod multikey;
 pub mod openapi;
-
+pub mod proof;
+pub mod representation;
+pub mod resolver;
+pub mod state;
@@ -1,5 +1,17 @@
+mod attribute;
+mod bbs;
+mod bbs2023;
+mod cryptosuite;
 mod handlers;
+mod jcs;
+mod jsonld;
+mod keystore;
+mod multikey;
 mod openapi;
+mod proof;
+mod representation;
+mod resolver;
+mod state;
 
 use axum::{Json, Router, routing::{get, post}, http::StatusCode, response::IntoResponse};
 use tokio::net::TcpListener;
@@ -9,6 +21,14 @@ use serde_json::json;
 
 use tracing::{info};
 
+use keystore::{
+    KeyAlgorithm, KeyStore, DEFAULT_KEY_STORE_PATH, KEY_STORE_PATH_ENV,
+    DEFAULT_KEY_RETENTION_DAYS, KEY_RETENTION_DAYS_ENV,
+};
+use jsonld::context::JSONLD_CONTEXT_DIR_ENV;
+use resolver::{DidKeyResolver, DidWebResolver, TrustedKeysResolver, DID_WEB_ROOT_ENV, TRUSTED_DID_KEYS_ENV, TRUSTED_KEYS_PATH_ENV};
+use state::{AppState, ADMIN_TOKEN_ENV, DEFAULT_ISSUER_DID, ISSUER_DID_ENV};
+
 use flexi_logger::{Logger, Criterion, Naming, Cleanup, FileSpec, Duplicate};
 
 // use utoipa_swagger_ui::SwaggerUi;
@@ -28,6 +48,50 @@ async fn main() {
         return;
     }
 
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("keys") {
+        keys_command(&args[2..]);
+        return;
+    }
+
+    let key_store = load_key_store();
+    info!("🔑 Using signing key '{}'", key_store.active_key_id());
+
+    let issuer = std::env::var(ISSUER_DID_ENV)
+        .unwrap_or_else(|_| DEFAULT_ISSUER_DID.to_string());
+    info!("🪪 Publishing keys as '{}'", issuer);
+
+    let mut state = AppState::new(key_store).with_issuer(issuer);
+
+    if let Ok(path) = std::env::var(TRUSTED_KEYS_PATH_ENV) {
+        let trusted = TrustedKeysResolver::load(&path).expect("Failed to load the trusted keys file");
+        state = state.with_resolver(trusted);
+    }
+
+    if let Ok(dids) = std::env::var(TRUSTED_DID_KEYS_ENV) {
+        let trusted: Vec<&str> = dids.split(',').map(str::trim).filter(|did| !did.is_empty()).collect();
+        info!("🔓 Trusting {} did:key identifiers", trusted.len());
+        state = state.with_resolver(DidKeyResolver::new(trusted));
+    }
+
+    if let Ok(root) = std::env::var(DID_WEB_ROOT_ENV) {
+        info!("🌐 Resolving did:web documents from {}", root);
+        state = state.with_resolver(DidWebResolver::new(root));
+    }
+
+    match std::env::var(ADMIN_TOKEN_ENV) {
+        Ok(token) if !token.is_empty() => {
+            info!("🛡️ Key administration endpoints enabled");
+            state = state.with_admin_token(token);
+        }
+        _ => info!("🛡️ Key administration endpoints disabled, set {} to enable them", ADMIN_TOKEN_ENV),
+    }
+
+    if let Ok(dir) = std::env::var(JSONLD_CONTEXT_DIR_ENV) {
+        info!("📚 Loading cached JSON-LD contexts from {}", dir);
+        state = state.with_context_dir(dir);
+    }
+
     let _api = openapi::ApiDoc::openapi();
 
     // TODO: Fix SwaggerUi integration
@@ -36,9 +100,16 @@ async fn main() {
     let app = Router::new()
         .route("/info", get(handlers::version::service_info))
         .route("/sign", post(handlers::sign::sign_handler))
+        .route("/sign/batch", post(handlers::sign::sign_batch_handler))
         .route("/config", post(handlers::config::config_handler))
         .route("/verify", post(handlers::verify::verify_handler))
-        .fallback(fallback_handler);
+        .route("/derive", post(handlers::derive::derive_handler))
+        .route("/keys", get(handlers::keys::keys_handler))
+        .route("/.well-known/did.json", get(handlers::keys::did_document_handler))
+        .route("/admin/keys", post(handlers::admin::create_key_handler))
+        .route("/admin/keys/{id}/activate", post(handlers::admin::activate_key_handler))
+        .fallback(fallback_handler)
+        .with_state(state);
         // .merge(swagger_router);
 
 
@@ -48,6 +119,70 @@ async fn main() {
     axum::serve(listener, app).await.unwrap();
 }
 
+fn load_key_store() -> KeyStore {
+    let key_store_path = std::env::var(KEY_STORE_PATH_ENV)
+        .unwrap_or_else(|_| DEFAULT_KEY_STORE_PATH.to_string());
+    let retention_days = std::env::var(KEY_RETENTION_DAYS_ENV).ok()
+        .map(|days| days.parse::<i64>().expect("KEY_RETENTION_DAYS must be a number of days"))
+        .unwrap_or(DEFAULT_KEY_RETENTION_DAYS);
+
+    KeyStore::load_or_generate(&key_store_path)
+        .expect("Failed to load the signing key store")
+        .with_retention(chrono::Duration::days(retention_days))
+}
+
+/// `signer keys <list | create [--activate] [--algorithm <Ed25519|P-256|P-384|Bls12381G2|ML-DSA-65>] | activate <id>>`
+///
+/// Manages the key store file offline; a running service picks up the
+/// changes on restart, or use the `/admin/keys` endpoints, enabled by
+/// `ADMIN_TOKEN`, instead.
+fn keys_command(args: &[String]) {
+    let mut key_store = load_key_store();
+
+    let result = match args.first().map(String::as_str) {
+        Some("list") | None => Ok(()),
+        Some("create") => {
+            let activate = args.iter().any(|arg| arg == "--activate");
+            let algorithm = match args.iter().position(|arg| arg == "--algorithm") {
+                Some(i) => match args.get(i + 1).and_then(|name| KeyAlgorithm::from_name(name)) {
+                    Some(algorithm) => algorithm,
+                    None => {
+                        eprintln!("Usage: signer keys create [--activate] [--algorithm <Ed25519|P-256|P-384|Bls12381G2|ML-DSA-65>]");
+                        std::process::exit(2);
+                    }
+                },
+                None => KeyAlgorithm::Ed25519,
+            };
+            key_store.create_key(algorithm)
+                .map(|key| key.id.clone())
+                .and_then(|id| if activate { key_store.activate(&id).map(|_| ()) } else { Ok(()) })
+        }
+        Some("activate") => match args.get(1) {
+            Some(id) => key_store.activate(id).map(|_| ()),
+            None => {
+                eprintln!("Usage: signer keys activate <id>");
+                std::process::exit(2);
+            }
+        },
+        Some(other) => {
+            eprintln!("Unknown keys subcommand '{}'. Use list, create [--activate] [--algorithm <name>] or activate <id>", other);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    for key in key_store.keys() {
+        let until = key_store.verifiable_until(key)
+            .map(|ts| format!(" (verifiable until {})", ts.to_rfc3339()))
+            .unwrap_or_default();
+        println!("{}\t{}\t{}\t{}{}", key.id, key.algorithm.as_str(), key.status.as_str(), key.created, until);
+    }
+}
+
 async fn fallback_handler() -> impl IntoResponse {
     let body = json!({ "error": "Endpoint not implemented" });
     (StatusCode::NOT_FOUND, Json(body))
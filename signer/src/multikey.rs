@@ -0,0 +1,156 @@
+use ed25519_dalek::VerifyingKey;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde_json::Value;
+use crate::bbs;
+use crate::resolver::PublicKey;
+
+/// Multicodec prefix of an Ed25519 public key (`ed25519-pub`, varint encoded).
+pub const ED25519_PUB_PREFIX: [u8; 2] = [0xed, 0x01];
+
+/// Multicodec prefix of a compressed P-256 public key (`p256-pub`, varint encoded).
+pub const P256_PUB_PREFIX: [u8; 2] = [0x80, 0x24];
+
+/// Multicodec prefix of a compressed P-384 public key (`p384-pub`, varint encoded).
+pub const P384_PUB_PREFIX: [u8; 2] = [0x81, 0x24];
+
+/// Multicodec prefix of a compressed BLS12-381 G2 public key (`bls12_381-g2-pub`, varint encoded).
+pub const BLS12381_G2_PUB_PREFIX: [u8; 2] = [0xeb, 0x01];
+
+/// Multicodec prefix of an ML-DSA-65 public key (`mldsa-65-pub`, varint encoded).
+pub const MLDSA65_PUB_PREFIX: [u8; 2] = [0x91, 0x24];
+
+/// Encodes a public key as a base58-btc Multikey (`z6Mk…`, `zDn…`, `z82…`, `zUC7…`, `z5Fb…`).
+pub fn encode(key: &PublicKey) -> String {
+    let (prefix, point) = match key {
+        PublicKey::Ed25519(key) => return encode_ed25519(key),
+        PublicKey::P256(key) => (P256_PUB_PREFIX, key.to_encoded_point(true).as_bytes().to_vec()),
+        PublicKey::P384(key) => (P384_PUB_PREFIX, key.to_encoded_point(true).as_bytes().to_vec()),
+        PublicKey::Bls12381G2(key) => (BLS12381_G2_PUB_PREFIX, key.to_bytes().to_vec()),
+        PublicKey::MlDsa65(key) => (MLDSA65_PUB_PREFIX, key.as_bytes().to_vec()),
+    };
+
+    let mut bytes = prefix.to_vec();
+    bytes.extend_from_slice(&point);
+    format!("z{}", bs58::encode(bytes).into_string())
+}
+
+/// Encodes an Ed25519 public key as a base58-btc Multikey (`z6Mk…`).
+pub fn encode_ed25519(key: &VerifyingKey) -> String {
+    let mut bytes = ED25519_PUB_PREFIX.to_vec();
+    bytes.extend_from_slice(key.as_bytes());
+    format!("z{}", bs58::encode(bytes).into_string())
+}
+
+/// Encodes a public key as a JSON Web Key (RFC 7518 / RFC 8037; ML-DSA keys
+/// as `AKP` keys of the JOSE ML-DSA draft).
+pub fn jwk(key: &PublicKey) -> Value {
+    let (crv, point) = match key {
+        PublicKey::Ed25519(key) => return ed25519_jwk(key),
+        PublicKey::P256(key) => ("P-256", key.to_encoded_point(false).as_bytes().to_vec()),
+        PublicKey::P384(key) => ("P-384", key.to_encoded_point(false).as_bytes().to_vec()),
+        PublicKey::Bls12381G2(key) => {
+            // Uncompressed G2 point: x || y, without SEC1 prefix
+            let point = key.to_uncompressed();
+            let (x, y) = point.split_at(point.len() / 2);
+            return serde_json::json!({
+                "kty": "EC",
+                "crv": "BLS12381G2",
+                "x": URL_SAFE_NO_PAD.encode(x),
+                "y": URL_SAFE_NO_PAD.encode(y)
+            });
+        }
+        PublicKey::MlDsa65(key) => {
+            return serde_json::json!({
+                "kty": "AKP",
+                "alg": "ML-DSA-65",
+                "pub": URL_SAFE_NO_PAD.encode(key.as_bytes())
+            });
+        }
+    };
+
+    // Uncompressed SEC1 point: 0x04 || x || y
+    let (x, y) = point[1..].split_at((point.len() - 1) / 2);
+
+    serde_json::json!({
+        "kty": "EC",
+        "crv": crv,
+        "x": URL_SAFE_NO_PAD.encode(x),
+        "y": URL_SAFE_NO_PAD.encode(y)
+    })
+}
+
+/// Encodes an Ed25519 public key as a JSON Web Key (RFC 8037).
+pub fn ed25519_jwk(key: &VerifyingKey) -> Value {
+    serde_json::json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "x": URL_SAFE_NO_PAD.encode(key.as_bytes())
+    })
+}
+
+/// Decodes a base58-btc Multikey of any supported algorithm.
+pub fn decode(multibase: &str) -> Option<PublicKey> {
+    let encoded = multibase.strip_prefix('z')?;
+    let bytes = bs58::decode(encoded).into_vec().ok()?;
+
+    if let Some(key) = bytes.strip_prefix(&ED25519_PUB_PREFIX[..]) {
+        let key: [u8; 32] = key.try_into().ok()?;
+        return VerifyingKey::from_bytes(&key).ok().map(PublicKey::Ed25519);
+    }
+
+    if let Some(point) = bytes.strip_prefix(&P256_PUB_PREFIX[..]) {
+        return p256::ecdsa::VerifyingKey::from_sec1_bytes(point).ok().map(PublicKey::P256);
+    }
+
+    if let Some(point) = bytes.strip_prefix(&P384_PUB_PREFIX[..]) {
+        return p384::ecdsa::VerifyingKey::from_sec1_bytes(point).ok().map(PublicKey::P384);
+    }
+
+    if let Some(point) = bytes.strip_prefix(&BLS12381_G2_PUB_PREFIX[..]) {
+        return bbs::PublicKey::from_bytes(point).map(PublicKey::Bls12381G2);
+    }
+
+    if let Some(key) = bytes.strip_prefix(&MLDSA65_PUB_PREFIX[..]) {
+        return mldsa::VerifyingKey::from_bytes(key).ok().map(|key| PublicKey::MlDsa65(Box::new(key)));
+    }
+
+    None
+}
+
+/// Decodes an Ed25519 (`OKP`), NIST curve or BLS12-381 G2 (`EC`) or ML-DSA
+/// (`AKP`) JSON Web Key.
+pub fn decode_jwk(jwk: &Value) -> Option<PublicKey> {
+    let coordinate = |name: &str| URL_SAFE_NO_PAD.decode(jwk.get(name)?.as_str()?).ok();
+
+    if jwk.get("kty")?.as_str()? == "AKP" {
+        return match jwk.get("alg")?.as_str()? {
+            "ML-DSA-65" => mldsa::VerifyingKey::from_bytes(&coordinate("pub")?)
+                .ok()
+                .map(|key| PublicKey::MlDsa65(Box::new(key))),
+            _ => None,
+        };
+    }
+
+    match (jwk.get("kty")?.as_str()?, jwk.get("crv")?.as_str()?) {
+        ("OKP", "Ed25519") => {
+            let key: [u8; 32] = coordinate("x")?.try_into().ok()?;
+            VerifyingKey::from_bytes(&key).ok().map(PublicKey::Ed25519)
+        }
+        ("EC", crv @ ("P-256" | "P-384")) => {
+            let mut point = vec![0x04];
+            point.extend(coordinate("x")?);
+            point.extend(coordinate("y")?);
+
+            match crv {
+                "P-256" => p256::ecdsa::VerifyingKey::from_sec1_bytes(&point).ok().map(PublicKey::P256),
+                _ => p384::ecdsa::VerifyingKey::from_sec1_bytes(&point).ok().map(PublicKey::P384),
+            }
+        }
+        ("EC", "BLS12381G2") => {
+            let mut point = coordinate("x")?;
+            point.extend(coordinate("y")?);
+            bbs::PublicKey::from_uncompressed(&point).map(PublicKey::Bls12381G2)
+        }
+        _ => None,
+    }
+}
@@ -1,19 +1,45 @@
 use utoipa::OpenApi;
-use crate::handlers::{version, sign, verify};
+use crate::handlers::{version, sign, verify, derive, keys, admin};
+use crate::{attribute, cryptosuite, keystore, proof, representation};
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         version::service_info,
         sign::sign_handler,
-        verify::verify_handler
+        sign::sign_batch_handler,
+        verify::verify_handler,
+        derive::derive_handler,
+        keys::keys_handler,
+        keys::did_document_handler,
+        admin::create_key_handler,
+        admin::activate_key_handler
     ),
     components(
         schemas(
             version::ServiceInfo,
             verify::VerifyRequest,
             verify::VerifyResult,
-            verify::VerificationStatus
+            verify::VerificationStatus,
+            verify::ProofSetPolicy,
+            verify::ProofResult,
+            verify::FailureReason,
+            verify::FreshnessPolicy,
+            derive::DeriveRequest,
+            proof::ProofValueEncoding,
+            representation::Representation,
+            sign::SignBatchResult,
+            sign::EntityResult,
+            sign::EntityStatus,
+            attribute::AttributeType,
+            keys::VerificationMethodType,
+            keys::VerificationMethod,
+            keys::PublishedKey,
+            keys::KeysResponse,
+            keystore::KeyStatus,
+            keystore::KeyAlgorithm,
+            cryptosuite::Cryptosuite,
+            admin::CreateKeyRequest
         )
     ),
     tags(
@@ -0,0 +1,250 @@
+use base64::{engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine as _};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use sha2::{Digest, Sha256, Sha384};
+use crate::attribute::{self, AttributeError};
+use crate::cryptosuite::Cryptosuite;
+use crate::jsonld::JsonLdError;
+use crate::jsonld::expand::escape_pointer;
+use crate::jsonld::select::parse_pointer;
+use crate::keystore::KeyAlgorithm;
+use crate::jsonld::context::ContextLoader;
+
+/// Name of the attribute member holding the proof of an attribute.
+pub const PROOF_MEMBER: &str = "ngsildproof";
+
+/// Encoding of a `proofValue`.
+#[derive(Serialize, ToSchema, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofValueEncoding {
+    /// Multibase base58-btc (`z…`) or base64url (`u…`), as required by the Data Integrity suites.
+    Multibase,
+    /// Plain base64, as emitted by earlier versions of the service.
+    Base64,
+}
+
+/// Encodes a signature as a multibase base58-btc `proofValue`.
+pub fn encode_proof_value(signature: &[u8]) -> String {
+    format!("z{}", bs58::encode(signature).into_string())
+}
+
+/// Decodes a `proofValue` in either multibase (base58-btc, or base64url for
+/// `bbs-2023`) or legacy base64 form.
+pub fn decode_proof_value(proof_value: &str) -> Option<(Vec<u8>, ProofValueEncoding)> {
+    // Base64 signatures may start with `z` too, but then contain characters
+    // (`+`, `/`, `=`, `0`, `O`, `I`, `l`) outside the base58 alphabet
+    if let Some(encoded) = proof_value.strip_prefix('z')
+        && let Ok(bytes) = bs58::decode(encoded).into_vec() {
+        return Some((bytes, ProofValueEncoding::Multibase));
+    }
+
+    // Legacy Ed25519 signatures are padded, which base64url without padding rejects
+    if let Some(encoded) = proof_value.strip_prefix('u')
+        && let Ok(bytes) = URL_SAFE_NO_PAD.decode(encoded) {
+        return Some((bytes, ProofValueEncoding::Multibase));
+    }
+
+    STANDARD.decode(proof_value).ok().map(|bytes| (bytes, ProofValueEncoding::Base64))
+}
+
+/// Returns a copy of an attribute without its `ngsildproof` member, nor
+/// those of its sub-attributes, which are signed on their own.
+pub fn attribute_without_proof(attribute: &Map<String, Value>) -> Value {
+    fn strip(value: &Value) -> Value {
+        match value {
+            Value::Object(obj) => Value::Object(obj.iter()
+                .filter(|(key, _)| *key != PROOF_MEMBER)
+                .map(|(key, value)| (key.clone(), strip(value)))
+                .collect()),
+            Value::Array(items) => Value::Array(items.iter().map(strip).collect()),
+            other => other.clone(),
+        }
+    }
+
+    strip(&Value::Object(attribute.clone()))
+}
+
+/// The content sealed by the proofs of an attribute (or entity): the
+/// attribute without its proofs, its values in their canonical form.
+pub fn sealed_content(attribute: &Map<String, Value>) -> Result<Value, AttributeError> {
+    attribute::canonicalize(&attribute_without_proof(attribute))
+}
+
+/// Path to the attribute named by an entry of `properties_to_sign`: a
+/// top-level attribute, or a sub-attribute given as a JSON pointer
+/// (`/address/verifiedBy`) or a dotted path (`address.verifiedBy`).
+pub fn attribute_path(entity: &Map<String, Value>, property: &str) -> Vec<String> {
+    if entity.contains_key(property) {
+        return vec![property.to_string()];
+    }
+
+    if property.starts_with('/')
+        && let Ok(tokens) = parse_pointer(property) {
+        return tokens;
+    }
+
+    property.split('.').map(String::from).collect()
+}
+
+/// JSON pointer to the attribute at `path` within its entity.
+pub fn attribute_pointer(path: &[String]) -> String {
+    path.iter().map(|token| format!("/{}", escape_pointer(token))).collect()
+}
+
+/// JSON pointers to every instance of the attribute at `path`: an attribute,
+/// or one of its parents, may be an array of instances told apart by their
+/// `datasetId`.
+pub fn instance_pointers(entity: &Value, path: &[String]) -> Vec<String> {
+    path.iter().fold(vec![String::new()], |pointers, name| {
+        pointers.into_iter()
+            .flat_map(|parent| {
+                let pointer = format!("{}/{}", parent, escape_pointer(name));
+                match entity.pointer(&pointer) {
+                    Some(Value::Array(instances)) => (0..instances.len())
+                        .map(|i| format!("{}/{}", pointer, i))
+                        .collect(),
+                    _ => vec![pointer],
+                }
+            })
+            .collect()
+    })
+}
+
+/// Returns the proofs of an `ngsildproof`: its `proof` object, or each
+/// object of a proof set.
+pub fn proof_set(ngsildproof: &Map<String, Value>) -> Vec<&Map<String, Value>> {
+    match ngsildproof.get("proof") {
+        Some(Value::Object(proof)) => vec![proof],
+        Some(Value::Array(proofs)) => proofs.iter().filter_map(Value::as_object).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns a fresh `urn:uuid:` identifier (UUID version 4) for a proof, so
+/// that later proofs of a chain can reference it.
+pub fn new_proof_id() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("urn:uuid:{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Identifiers referenced by the `previousProof` of a proof.
+pub fn previous_proof_ids(proof: &Map<String, Value>) -> Vec<&str> {
+    match proof.get("previousProof") {
+        Some(Value::String(id)) => vec![id.as_str()],
+        Some(Value::Array(ids)) => ids.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Builds the document covered by a proof of a chain: the attribute document
+/// together with the previous proofs it endorses, as nodes of the same graph.
+pub fn chained_document(document: &Value, previous: &[&Map<String, Value>]) -> Value {
+    if previous.is_empty() {
+        return document.clone();
+    }
+
+    let mut graph = vec![document.clone()];
+    graph.extend(previous.iter().map(|proof| Value::Object((*proof).clone())));
+
+    let mut chained = Map::new();
+    chained.insert("@graph".into(), Value::Array(graph));
+    Value::Object(chained)
+}
+
+/// Returns the proof options, i.e. the proof object without its `proofValue`.
+pub fn proof_options(proof: &Map<String, Value>) -> Value {
+    let mut options = proof.clone();
+    options.remove("proofValue");
+    Value::Object(options)
+}
+
+/// Builds the document sealed by an attribute proof: a one-attribute entity,
+/// so that the id and type of the enclosing entity and the attribute name are
+/// signed together with the attribute. A sub-attribute is nested under the
+/// names of its parents, which are signed as well.
+///
+/// The empty path designates the entity itself, sealed whole but for its
+/// `@context`, which the proof carries.
+pub fn attribute_document(entity_id: &str, entity_type: &str, path: &[String], attribute: &Value) -> Value {
+    if path.is_empty() {
+        let mut document = attribute.as_object().cloned().unwrap_or_default();
+        document.remove("@context");
+        return Value::Object(document);
+    }
+
+    let mut document = Map::new();
+    document.insert("id".into(), Value::String(entity_id.into()));
+    document.insert("type".into(), Value::String(entity_type.into()));
+
+    let nested = path.iter().skip(1).rev().fold(attribute.clone(), |value, name| {
+        let mut parent = Map::new();
+        parent.insert(name.clone(), value);
+        Value::Object(parent)
+    });
+    if let Some(name) = path.first() {
+        document.insert(name.clone(), nested);
+    }
+    Value::Object(document)
+}
+
+/// Builds the data covered by a signature (the suite's hashData).
+///
+/// The document and the proof options, both interpreted with the `@context`
+/// of the proof, are canonicalized by the cryptosuite and hashed with SHA-256
+/// (SHA-384 for P-384 keys); the result is `hash(proof options) || hash(document)`.
+pub fn signing_input(
+    suite: Cryptosuite,
+    algorithm: KeyAlgorithm,
+    document: &Value,
+    options: &Value,
+    loader: &ContextLoader,
+) -> Result<Vec<u8>, JsonLdError> {
+    let (document, options) = with_proof_context(suite, document, options);
+
+    let canonical_options = suite.canonicalize(&options, loader)?;
+    let canonical_document = suite.canonicalize(&document, loader)?;
+
+    let mut hash_data = digest(algorithm, canonical_options.as_bytes());
+    hash_data.extend(digest(algorithm, canonical_document.as_bytes()));
+
+    Ok(hash_data)
+}
+
+/// Returns the document and the proof options, both with the `@context` of
+/// the proof.
+pub fn with_proof_context(suite: Cryptosuite, document: &Value, options: &Value) -> (Value, Value) {
+    let mut options = options.clone();
+
+    // Proofs without a context are read with the default context of the suite
+    if options.get("@context").is_none()
+        && let Some(context) = suite.proof_context(None) {
+        options["@context"] = context;
+    }
+
+    let mut document = document.clone();
+    if let Some(context) = options.get("@context") {
+        document["@context"] = context.clone();
+    }
+
+    (document, options)
+}
+
+/// Hash of the canonical forms signed by `algorithm`.
+///
+/// SHA-384 for ML-DSA-65 is a choice of this service's own
+/// `mldsa65-rdfc-2024` suite, which no specification defines: ML-DSA-65 is in
+/// NIST security category 3, like P-384, which the ECDSA suites hash with SHA-384.
+fn digest(algorithm: KeyAlgorithm, data: &[u8]) -> Vec<u8> {
+    match algorithm {
+        KeyAlgorithm::P384 | KeyAlgorithm::MlDsa65 => Sha384::digest(data).to_vec(),
+        KeyAlgorithm::Ed25519 | KeyAlgorithm::P256 | KeyAlgorithm::Bls12381G2 => Sha256::digest(data).to_vec(),
+    }
+}
@@ -0,0 +1,192 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use crate::proof::PROOF_MEMBER;
+
+/// Members of an entity that are not attributes.
+const ENTITY_MEMBERS: [&str; 8] = ["id", "type", "@context", "scope", "createdAt", "modifiedAt", "deletedAt", PROOF_MEMBER];
+
+/// Members of an attribute that are not sub-attributes (NGSI-LD 1.8): its
+/// value in each attribute type, the previous value of notifications, the
+/// entities of linked entity retrieval, the values of the simplified temporal
+/// representation, and the system and instance members.
+pub(crate) const ATTRIBUTE_MEMBERS: [&str; 34] = [
+    "type", "value", "object", "objectType", "languageMap", "lang", "vocab", "json", "valueList", "objectList",
+    "previousValue", "previousObject", "previousLanguageMap", "previousVocab", "previousJson",
+    "previousValueList", "previousObjectList",
+    "entity", "entityList",
+    "values", "objects", "languageMaps", "vocabs", "jsons", "valueLists", "objectLists",
+    "datasetId", "observedAt", "unitCode", "createdAt", "modifiedAt", "deletedAt", "instanceId", "scope",
+];
+
+/// GeoJSON geometry types, whose values make a concise attribute a `GeoProperty`.
+const GEOMETRY_TYPES: [&str; 7] = [
+    "Point", "MultiPoint", "LineString", "MultiLineString", "Polygon", "MultiPolygon", "GeometryCollection",
+];
+
+/// NGSI-LD representation of the entities of a payload.
+#[derive(Deserialize, ToSchema, Clone, Copy, Debug, PartialEq)]
+pub enum Representation {
+    /// Every attribute is an object with its `type`.
+    #[serde(rename = "normalized")]
+    Normalized,
+    /// Attribute types are left out wherever they can be inferred, and
+    /// attributes with a value only are given as that value.
+    #[serde(rename = "concise")]
+    Concise,
+    /// Attributes are given as their value only.
+    #[serde(rename = "keyValues")]
+    KeyValues,
+}
+
+/// Tells the representation of an entity from the shape of its attributes.
+pub fn detect(entity: &Map<String, Value>) -> Representation {
+    let attributes: Vec<&Value> = entity.iter()
+        .filter(|(key, _)| !ENTITY_MEMBERS.contains(&key.as_str()))
+        .flat_map(|(_, value)| match value {
+            Value::Array(items) if items.iter().all(is_attribute) => items.iter().collect(),
+            other => vec![other],
+        })
+        .collect();
+
+    if attributes.iter().all(|value| value.get("type").is_some_and(|_| is_attribute(value))) {
+        Representation::Normalized
+    } else if attributes.iter().any(|value| is_attribute(value)) {
+        Representation::Concise
+    } else {
+        Representation::KeyValues
+    }
+}
+
+/// Converts an entity to the normalized representation, in which it is signed
+/// and verified. Normalized entities are returned unchanged.
+pub fn normalize(entity: &Map<String, Value>) -> Map<String, Value> {
+    entity.iter()
+        .map(|(key, value)| match ENTITY_MEMBERS.contains(&key.as_str()) {
+            true => (key.clone(), value.clone()),
+            false => (key.clone(), normalize_attribute(value)),
+        })
+        .collect()
+}
+
+/// Converts a normalized entity to the concise representation.
+pub fn concise(entity: &Map<String, Value>) -> Map<String, Value> {
+    entity.iter()
+        .map(|(key, value)| match ENTITY_MEMBERS.contains(&key.as_str()) {
+            true => (key.clone(), value.clone()),
+            false => (key.clone(), concise_attribute(value)),
+        })
+        .collect()
+}
+
+fn normalize_attribute(value: &Value) -> Value {
+    match value {
+        // Instances of a multi-attribute
+        Value::Array(items) if !items.is_empty() && items.iter().all(is_attribute) => {
+            Value::Array(items.iter().map(normalize_attribute).collect())
+        }
+        Value::Object(obj) if is_attribute(value) => {
+            let mut attribute = Map::new();
+            if !obj.contains_key("type") {
+                attribute.insert("type".into(), Value::String(infer_type(obj).into()));
+            }
+
+            for (key, member) in obj {
+                let member = match ATTRIBUTE_MEMBERS.contains(&key.as_str()) || key == PROOF_MEMBER {
+                    true => member.clone(),
+                    false => normalize_attribute(member),
+                };
+                attribute.insert(key.clone(), member);
+            }
+            Value::Object(attribute)
+        }
+        // A value alone
+        other => {
+            let attribute_type = if is_geometry(other) { "GeoProperty" } else { "Property" };
+            serde_json::json!({ "type": attribute_type, "value": other })
+        }
+    }
+}
+
+fn concise_attribute(value: &Value) -> Value {
+    match value {
+        // Instances of a multi-attribute stay objects, or the array would be
+        // taken for a value
+        Value::Array(items) => Value::Array(items.iter().map(|item| concise_instance(item, false)).collect()),
+        other => concise_instance(other, true),
+    }
+}
+
+fn concise_instance(value: &Value, collapse: bool) -> Value {
+    let Value::Object(obj) = value else {
+        return value.clone();
+    };
+
+    let mut attribute = Map::new();
+    for (key, member) in obj {
+        let member = match ATTRIBUTE_MEMBERS.contains(&key.as_str()) || key == PROOF_MEMBER {
+            true => member.clone(),
+            false => concise_attribute(member),
+        };
+        attribute.insert(key.clone(), member);
+    }
+
+    // The type goes whenever normalizing infers it back
+    if attribute.get("type").and_then(Value::as_str) == Some(infer_type(&attribute)) {
+        attribute.remove("type");
+    }
+
+    // So does the object around a value alone, unless the value would be taken
+    // for an attribute
+    if collapse
+        && attribute.len() == 1
+        && let Some(value) = attribute.get("value")
+        && normalize_attribute(value) == normalize_attribute(&Value::Object(attribute.clone())) {
+        return value.clone();
+    }
+
+    Value::Object(attribute)
+}
+
+/// Whether `value` is an attribute, in normalized or concise form, rather
+/// than the value of an attribute.
+fn is_attribute(value: &Value) -> bool {
+    let Some(obj) = value.as_object() else {
+        return false;
+    };
+
+    match obj.get("type").and_then(Value::as_str) {
+        Some(attribute_type) => attribute_type.ends_with("Property") || attribute_type.ends_with("Relationship"),
+        None => ["value", "object", "languageMap", "vocab", "json", "valueList", "objectList"]
+            .iter()
+            .any(|member| obj.contains_key(*member)),
+    }
+}
+
+/// Type of a concise attribute, told from its value member.
+fn infer_type(attribute: &Map<String, Value>) -> &'static str {
+    if attribute.contains_key("object") {
+        "Relationship"
+    } else if attribute.contains_key("objectList") {
+        "ListRelationship"
+    } else if attribute.contains_key("languageMap") {
+        "LanguageProperty"
+    } else if attribute.contains_key("vocab") {
+        "VocabProperty"
+    } else if attribute.contains_key("json") {
+        "JsonProperty"
+    } else if attribute.contains_key("valueList") {
+        "ListProperty"
+    } else if attribute.get("value").is_some_and(is_geometry) {
+        "GeoProperty"
+    } else {
+        "Property"
+    }
+}
+
+fn is_geometry(value: &Value) -> bool {
+    value.get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|geometry_type| GEOMETRY_TYPES.contains(&geometry_type))
+        && (value.get("coordinates").is_some() || value.get("geometries").is_some())
+}
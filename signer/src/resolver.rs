@@ -0,0 +1,271 @@
+use ed25519_dalek::{Signature, Verifier};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+use crate::bbs;
+use crate::keystore::{KeyAlgorithm, KeyStore, KeyStoreError};
+use crate::multikey;
+
+/// Environment variable pointing to a file of statically trusted keys.
+pub const TRUSTED_KEYS_PATH_ENV: &str = "TRUSTED_KEYS_PATH";
+
+/// Environment variable listing the trusted `did:key` DIDs, comma-separated.
+pub const TRUSTED_DID_KEYS_ENV: &str = "TRUSTED_DID_KEYS";
+
+/// Environment variable pointing to a directory of locally served DID documents.
+pub const DID_WEB_ROOT_ENV: &str = "DID_WEB_ROOT";
+
+/// A public key able to check proofs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PublicKey {
+    Ed25519(ed25519_dalek::VerifyingKey),
+    P256(p256::ecdsa::VerifyingKey),
+    P384(p384::ecdsa::VerifyingKey),
+    Bls12381G2(bbs::PublicKey),
+    MlDsa65(Box<mldsa::VerifyingKey>),
+}
+
+impl PublicKey {
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        match self {
+            PublicKey::Ed25519(_) => KeyAlgorithm::Ed25519,
+            PublicKey::P256(_) => KeyAlgorithm::P256,
+            PublicKey::P384(_) => KeyAlgorithm::P384,
+            PublicKey::Bls12381G2(_) => KeyAlgorithm::Bls12381G2,
+            PublicKey::MlDsa65(_) => KeyAlgorithm::MlDsa65,
+        }
+    }
+
+    /// Length in bytes of the raw signatures made with this key.
+    pub fn signature_length(&self) -> usize {
+        match self {
+            PublicKey::Ed25519(_) => ed25519_dalek::SIGNATURE_LENGTH,
+            PublicKey::P256(_) => 64,
+            PublicKey::P384(_) => 96,
+            PublicKey::Bls12381G2(_) => 80,
+            PublicKey::MlDsa65(_) => mldsa::SIGNATURE_LENGTH,
+        }
+    }
+
+    /// Checks a raw signature over `message`; ECDSA signatures are expected in
+    /// IEEE P1363 (`r || s`) form, BBS signatures over `message` alone,
+    /// ML-DSA signatures with an empty context string.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            PublicKey::Ed25519(key) => {
+                let signature: [u8; 64] = match signature.try_into() {
+                    Ok(arr) => arr,
+                    Err(_) => return false,
+                };
+
+                key.verify(message, &Signature::from_bytes(&signature)).is_ok()
+            }
+            PublicKey::P256(key) => p256::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+            PublicKey::P384(key) => p384::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+            PublicKey::Bls12381G2(key) => key.verify(&[], &[message.to_vec()], signature),
+            PublicKey::MlDsa65(key) => mldsa::Signature::from_bytes(signature)
+                .is_ok_and(|signature| key.verify(message, &[], &signature).is_ok()),
+        }
+    }
+}
+
+/// Maps the `verificationMethod` of a proof to the key that must verify it.
+pub trait KeyResolver: Send + Sync {
+    /// Returns `None` when this resolver does not know the verification method.
+    fn resolve(&self, verification_method: &str) -> Option<PublicKey>;
+}
+
+/// Tries a list of resolvers in order and returns the first match.
+#[derive(Default, Clone)]
+pub struct ResolverChain {
+    resolvers: Vec<Arc<dyn KeyResolver>>,
+}
+
+impl ResolverChain {
+    pub fn new() -> Self {
+        ResolverChain::default()
+    }
+
+    pub fn with(mut self, resolver: Arc<dyn KeyResolver>) -> Self {
+        self.resolvers.push(resolver);
+        self
+    }
+}
+
+impl KeyResolver for ResolverChain {
+    fn resolve(&self, verification_method: &str) -> Option<PublicKey> {
+        self.resolvers.iter().find_map(|r| r.resolve(verification_method))
+    }
+}
+
+/// Resolves the service's own keys (`<issuer>#<key-id>`) from the key store.
+pub struct KeyStoreResolver {
+    key_store: Arc<RwLock<KeyStore>>,
+    key_prefix: String,
+}
+
+impl KeyStoreResolver {
+    pub fn new(key_store: Arc<RwLock<KeyStore>>, issuer: &str) -> Self {
+        KeyStoreResolver { key_store, key_prefix: format!("{}#", issuer) }
+    }
+}
+
+impl KeyResolver for KeyStoreResolver {
+    fn resolve(&self, verification_method: &str) -> Option<PublicKey> {
+        let key_id = verification_method.strip_prefix(&self.key_prefix)?;
+        let key_store = self.key_store.read().unwrap();
+
+        key_store.verifiable_key(key_id).map(|k| k.public_key())
+    }
+}
+
+/// Resolves keys listed in a static trusted-keys file.
+///
+/// The file has the shape of a DID document fragment:
+/// `{ "verificationMethod": [ { "id": "…", "publicKeyMultibase": "z6Mk…" } ] }`.
+pub struct TrustedKeysResolver {
+    keys: Vec<(String, PublicKey)>,
+}
+
+impl TrustedKeysResolver {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KeyStoreError> {
+        let path = path.as_ref();
+        info!("Loading trusted keys from {}", path.display());
+
+        let content = fs::read_to_string(path)?;
+        let document: Value = serde_json::from_str(&content)
+            .map_err(|e| KeyStoreError::Format(e.to_string()))?;
+
+        let keys = verification_methods(&document)
+            .filter_map(|(id, method)| {
+                let key = decode_verification_method(method);
+                if key.is_none() {
+                    warn!("Ignoring trusted key '{}': unsupported key encoding", id);
+                }
+                key.map(|k| (id, k))
+            })
+            .collect();
+
+        Ok(TrustedKeysResolver { keys })
+    }
+}
+
+impl KeyResolver for TrustedKeysResolver {
+    fn resolve(&self, verification_method: &str) -> Option<PublicKey> {
+        self.keys.iter()
+            .find(|(id, _)| id == verification_method)
+            .map(|(_, key)| key.clone())
+    }
+}
+
+/// Decodes `did:key` identifiers, whose key is embedded in the DID itself.
+///
+/// Anyone can mint a `did:key` and sign with it, so only the DIDs of an
+/// allowlist resolve.
+pub struct DidKeyResolver {
+    trusted: Vec<String>,
+}
+
+impl DidKeyResolver {
+    pub fn new(trusted: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        DidKeyResolver { trusted: trusted.into_iter().map(Into::into).collect() }
+    }
+}
+
+impl KeyResolver for DidKeyResolver {
+    fn resolve(&self, verification_method: &str) -> Option<PublicKey> {
+        let did = verification_method.split('#').next()?;
+        if !self.trusted.iter().any(|trusted| trusted == did) {
+            return None;
+        }
+
+        multikey::decode(did.strip_prefix("did:key:")?)
+    }
+}
+
+/// Resolves `did:web` identifiers against DID documents served from a local
+/// directory laid out like the web server would (`<host>/.well-known/did.json`
+/// or `<host>/<path…>/did.json`).
+pub struct DidWebResolver {
+    root: PathBuf,
+}
+
+impl DidWebResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        DidWebResolver { root: root.into() }
+    }
+
+    fn document_path(&self, did: &str) -> Option<PathBuf> {
+        let mut segments = did.strip_prefix("did:web:")?.split(':');
+
+        // The host may carry a percent-encoded port, e.g. localhost%3A3000
+        let host = segments.next()?.replace("%3A", ":").replace("%3a", ":");
+        if host.is_empty() || host.contains('/') || host.starts_with('.') {
+            return None;
+        }
+
+        let mut path = self.root.join(host);
+        let mut has_path = false;
+        for segment in segments {
+            if segment.is_empty() || segment.starts_with('.') || segment.contains('/') {
+                return None;
+            }
+            path.push(segment);
+            has_path = true;
+        }
+
+        if !has_path {
+            path.push(".well-known");
+        }
+        path.push("did.json");
+
+        Some(path)
+    }
+}
+
+impl KeyResolver for DidWebResolver {
+    fn resolve(&self, verification_method: &str) -> Option<PublicKey> {
+        let did = verification_method.split('#').next()?;
+        let path = self.document_path(did)?;
+
+        let content = fs::read_to_string(&path).ok()?;
+        let document: Value = serde_json::from_str(&content).ok()?;
+
+        if document.get("id").and_then(Value::as_str) != Some(did) {
+            warn!("DID document at {} does not describe '{}'", path.display(), did);
+            return None;
+        }
+
+        verification_methods(&document)
+            .find(|(id, _)| id == verification_method)
+            .and_then(|(_, method)| decode_verification_method(method))
+    }
+}
+
+/// Iterates the verification methods of a DID document as `(absolute id, method)`.
+fn verification_methods(document: &Value) -> impl Iterator<Item = (String, &Value)> {
+    let did = document.get("id").and_then(Value::as_str).unwrap_or_default().to_string();
+
+    document.get("verificationMethod")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(move |method| {
+            let id = method.get("id").and_then(Value::as_str)?;
+            // Relative ids (`#key-1`) are resolved against the document id
+            let id = if id.starts_with('#') { format!("{}{}", did, id) } else { id.to_string() };
+            Some((id, method))
+        })
+}
+
+fn decode_verification_method(method: &Value) -> Option<PublicKey> {
+    if let Some(multibase) = method.get("publicKeyMultibase").and_then(Value::as_str) {
+        return multikey::decode(multibase);
+    }
+
+    method.get("publicKeyJwk").and_then(multikey::decode_jwk)
+}
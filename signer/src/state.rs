@@ -0,0 +1,85 @@
+use std::sync::{Arc, RwLock};
+use crate::jsonld::context::ContextLoader;
+use crate::keystore::{KeyAlgorithm, KeyStore, KeyStoreError, ServiceKey};
+use crate::resolver::{KeyResolver, KeyStoreResolver, ResolverChain};
+
+/// Default controller of the service keys, used in `verificationMethod` ids.
+pub const DEFAULT_ISSUER_DID: &str = "did:web:localhost%3A3000";
+
+/// Environment variable used to override the controller DID.
+pub const ISSUER_DID_ENV: &str = "ISSUER_DID";
+
+/// Environment variable holding the bearer token of the `/admin` endpoints,
+/// which are disabled without it.
+pub const ADMIN_TOKEN_ENV: &str = "ADMIN_TOKEN";
+
+/// Shared state handed to every axum handler.
+#[derive(Clone)]
+pub struct AppState {
+    pub key_store: Arc<RwLock<KeyStore>>,
+    pub issuer: String,
+    /// Resolvers consulted for keys that do not belong to the service; none
+    /// by default, so that only the service keys are trusted.
+    pub external_resolvers: ResolverChain,
+    /// Local cache of the JSON-LD contexts used to canonicalize attributes.
+    pub contexts: ContextLoader,
+    /// Bearer token required by the `/admin` endpoints; `None` disables them.
+    pub admin_token: Option<String>,
+}
+
+impl AppState {
+    pub fn new(key_store: KeyStore) -> Self {
+        AppState {
+            key_store: Arc::new(RwLock::new(key_store)),
+            issuer: DEFAULT_ISSUER_DID.to_string(),
+            external_resolvers: ResolverChain::new(),
+            contexts: ContextLoader::new(),
+            admin_token: None,
+        }
+    }
+
+    /// Adds a resolver for keys of other issuers (trusted keys, `did:web`, …).
+    pub fn with_resolver(mut self, resolver: impl KeyResolver + 'static) -> Self {
+        self.external_resolvers = self.external_resolvers.with(Arc::new(resolver));
+        self
+    }
+
+    /// Resolver used by `/verify`: the service keys first, then external keys.
+    pub fn resolver(&self) -> ResolverChain {
+        ResolverChain::new()
+            .with(Arc::new(KeyStoreResolver::new(self.key_store.clone(), &self.issuer)))
+            .with(Arc::new(self.external_resolvers.clone()))
+    }
+
+    /// Serves cached remote contexts from `dir` in addition to the bundled ones.
+    pub fn with_context_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.contexts = self.contexts.with_dir(dir);
+        self
+    }
+
+    /// Enables the `/admin` endpoints for requests bearing `token`.
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = issuer.into();
+        self
+    }
+
+    /// The active key for `algorithm`, generated on first use.
+    pub fn signing_key(&self, algorithm: KeyAlgorithm) -> Result<ServiceKey, KeyStoreError> {
+        if let Some(key) = self.key_store.read().unwrap().active_key_for(algorithm) {
+            return Ok(key.clone());
+        }
+
+        let mut key_store = self.key_store.write().unwrap();
+        key_store.ensure_active(algorithm).cloned()
+    }
+
+    /// Full `verificationMethod` id of a key of the service.
+    pub fn verification_method(&self, key_id: &str) -> String {
+        format!("{}#{}", self.issuer, key_id)
+    }
+}
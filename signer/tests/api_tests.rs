@@ -0,0 +1,1085 @@
+use serde_json::json;
+use signer::handlers::{sign::{sign_batch_handler, sign_handler, EntityStatus, SignQuery}, verify::verify_handler, verify::VerificationStatus};
+use signer::handlers::config::{config_handler, ConfigRequest};
+use signer::keystore::KeyStore;
+use signer::state::AppState;
+use axum::Json;
+use axum::extract::{Query, State};
+use serde_json::Value;
+
+mod common;
+
+use common::{test_state, TempPath};
+
+#[tokio::test]
+async fn test_sign_and_verify_ok() {
+    let (state, _key_store) = test_state("sign-and-verify");
+
+    let cfg = ConfigRequest {
+        entity_type: "Shop".to_string(),
+        properties_to_sign: vec!["address".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let document = json!({
+        "id": "urn:ngsi-ld:Shop:002",
+        "type": "Shop",
+        "address": {
+            "type": "Property",
+            "value": {
+                "streetAddress": ["Tiger Street 4", "al"],
+                "addressRegion": "Metropolis",
+                "addressLocality": "Cat City",
+                "postalCode": "42420"
+            }
+        }
+    });
+
+    let sign_req = json!({ "data": [document] });
+
+    // Sign the document
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(sign_req)).await;
+
+    let signed_value: Value = signed.unwrap().0["data"][0].clone();
+    assert!(signed_value["address"]["ngsildproof"].is_object());
+
+    // Now verify
+    let verify_req = json!({ "document": signed_value });
+
+    let result = verify_handler(State(state), Json(serde_json::from_value(verify_req).unwrap())).await;
+    let result_map = &result.0.results["urn:ngsi-ld:Shop:002"];
+
+    // Debug output
+    println!("Signed document: {}", serde_json::to_string_pretty(&signed_value).unwrap());
+    println!("Verification results: {:?}", result_map);
+
+    // Check if address key exists
+    if let Some(status) = result_map.get("address").map(|r| r.status) {
+        assert!(matches!(status, VerificationStatus::True));
+    } else {
+        panic!("Address key not found in verification results. Available keys: {:?}", result_map.keys().collect::<Vec<_>>());
+    }
+}
+
+#[tokio::test]
+async fn test_signing_key_is_persisted() {
+    let path = TempPath::new("persisted.json");
+
+    let first = KeyStore::load_or_generate(&*path).unwrap();
+    let second = KeyStore::load_or_generate(&*path).unwrap();
+
+    assert_eq!(first.active_key_id(), second.active_key_id());
+    assert_eq!(first.active_key().public_key(), second.active_key().public_key());
+}
+
+#[tokio::test]
+async fn test_signed_attribute_is_bound_to_its_entity() {
+    let (state, _key_store) = test_state("sealed");
+
+    let cfg = ConfigRequest {
+        entity_type: "Sensor".to_string(),
+        properties_to_sign: vec!["temperature".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Sensor:001",
+            "type": "Sensor",
+            "temperature": { "type": "Property", "value": 21.5 }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    // Copy the signed attribute onto another entity
+    let mut copied = signed.clone();
+    copied["id"] = json!("urn:ngsi-ld:Sensor:002");
+
+    // Tamper with the proof options
+    let mut backdated = signed.clone();
+    backdated["temperature"]["ngsildproof"]["proof"]["created"] = json!("2020-01-01T00:00:00+00:00");
+
+    // Move the signed value to another attribute of the same entity
+    let mut moved = signed.clone();
+    moved["humidity"] = moved["temperature"].clone();
+
+    let expectations = [
+        (signed, "temperature", true),
+        (copied, "temperature", false),
+        (backdated, "temperature", false),
+        (moved, "humidity", false),
+    ];
+
+    for (document, attribute, expected) in expectations {
+        let entity_id = document["id"].as_str().unwrap().to_string();
+        let request = json!({ "document": document });
+        let results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+        assert_eq!(matches!(results[&entity_id].get(attribute).map(|r| r.status), Some(VerificationStatus::True)), expected, "{}", attribute);
+    }
+}
+
+#[tokio::test]
+async fn test_proof_value_is_multibase_and_legacy_base64_verifies() {
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use signer::cryptosuite::Cryptosuite;
+    use signer::keystore::KeyAlgorithm;
+    use signer::proof::{self, ProofValueEncoding};
+
+    let (state, _key_store) = test_state("multibase");
+
+    let cfg = ConfigRequest {
+        entity_type: "Lamp".to_string(),
+        properties_to_sign: vec!["brightness".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Lamp:001",
+            "type": "Lamp",
+            "brightness": { "type": "Property", "value": 80 }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    let proof_value = signed["brightness"]["ngsildproof"]["proof"]["proofValue"].as_str().unwrap().to_string();
+    assert!(proof_value.starts_with('z'));
+
+    // Proofs stored by earlier versions, in the shape they were written:
+    // naming the cryptosuite, with the raw Ed25519 signature in plain base64
+    let signing_key = state.signing_key(KeyAlgorithm::Ed25519).unwrap();
+    let mut legacy_proof = json!({
+        "type": "DataIntegrityProof",
+        "created": "2025-06-01T10:00:00+00:00",
+        "verificationMethod": state.verification_method(&signing_key.id),
+        "cryptosuite": "eddsa-rdfc-2022",
+        "proofPurpose": "assertionMethod"
+    });
+    let attribute = json!({ "type": "Property", "value": 80 });
+    let document = proof::attribute_document("urn:ngsi-ld:Lamp:001", "Lamp", &["brightness".to_string()], &attribute);
+    let to_sign = proof::signing_input(
+        Cryptosuite::EddsaRdfc2022, KeyAlgorithm::Ed25519, &document, &legacy_proof, &state.contexts,
+    ).unwrap();
+    legacy_proof["proofValue"] = json!(STANDARD.encode(signing_key.sign(&to_sign).unwrap()));
+    let legacy = json!({
+        "id": "urn:ngsi-ld:Lamp:001",
+        "type": "Lamp",
+        "brightness": {
+            "type": "Property",
+            "value": 80,
+            "ngsildproof": {
+                "type": "Property",
+                "entityIdSealed": "urn:ngsi-ld:Lamp:001",
+                "entityTypeSealed": "Lamp",
+                "proof": legacy_proof
+            }
+        }
+    });
+
+    for (document, encoding) in [(signed, ProofValueEncoding::Multibase), (legacy, ProofValueEncoding::Base64)] {
+        let request = json!({ "document": document });
+        let result = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0;
+
+        assert!(matches!(result.results["urn:ngsi-ld:Lamp:001"].get("brightness").map(|r| r.status), Some(VerificationStatus::True)));
+        assert_eq!(result.encodings["urn:ngsi-ld:Lamp:001"].get("brightness"), Some(&encoding));
+    }
+}
+
+#[tokio::test]
+async fn test_proof_chain_endorses_previous_proofs() {
+    use signer::cryptosuite::Cryptosuite;
+
+    let (state, _key_store) = test_state("chain");
+
+    async fn sign_as(state: &AppState, cryptosuite: Cryptosuite, doc: Value) -> Value {
+        let cfg = ConfigRequest {
+            entity_type: "Beacon".to_string(),
+            properties_to_sign: vec!["signal".to_string()],
+            cryptosuite,
+            chain_proofs: true,
+            ..Default::default()
+        };
+        assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+
+        let doc = json!({ "data": [doc] });
+        sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone()
+    }
+
+    async fn verify(state: &AppState, document: Value) -> Vec<VerificationStatus> {
+        let request = json!({ "document": document });
+        let result = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0;
+        let statuses: Vec<VerificationStatus> = result.proofs.get("urn:ngsi-ld:Beacon:001").and_then(|p| p.get("signal"))
+            .map(|p| p.iter().map(|r| r.status).collect())
+            .unwrap_or_default();
+        assert_eq!(
+            matches!(result.results["urn:ngsi-ld:Beacon:001"].get("signal").map(|r| r.status), Some(VerificationStatus::True)),
+            !statuses.is_empty() && statuses.iter().all(|s| *s == VerificationStatus::True)
+        );
+        statuses
+    }
+
+    let doc = json!({
+        "id": "urn:ngsi-ld:Beacon:001",
+        "type": "Beacon",
+        "signal": { "type": "Property", "value": -67 }
+    });
+
+    // Sealed by the gateway, then counter-signed by the platform
+    let sealed = sign_as(&state, Cryptosuite::EddsaJcs2022, doc).await;
+    let endorsed = sign_as(&state, Cryptosuite::EddsaRdfc2022, sealed.clone()).await;
+
+    let proofs = endorsed["signal"]["ngsildproof"]["proof"].as_array().unwrap().clone();
+    assert_eq!(proofs.len(), 2);
+    assert_eq!(proofs[0], sealed["signal"]["ngsildproof"]["proof"]);
+    assert_eq!(proofs[1]["previousProof"], proofs[0]["id"]);
+    assert_eq!(verify(&state, endorsed.clone()).await, vec![VerificationStatus::True; 2]);
+
+    // Signing again with the same suite and key keeps the proofs as they are
+    let resigned = sign_as(&state, Cryptosuite::EddsaRdfc2022, endorsed.clone()).await;
+    assert_eq!(resigned, endorsed);
+
+    // A third proof endorses both
+    let archived = sign_as(&state, Cryptosuite::EcdsaRdfc2019, endorsed.clone()).await;
+    let last = &archived["signal"]["ngsildproof"]["proof"][2];
+    assert_eq!(last["previousProof"], json!([proofs[0]["id"], proofs[1]["id"]]));
+    assert_eq!(verify(&state, archived).await, vec![VerificationStatus::True; 3]);
+
+    // Altering an endorsed proof breaks it and every proof after it
+    let mut altered = endorsed.clone();
+    altered["signal"]["ngsildproof"]["proof"][0]["created"] = json!("2020-01-01T00:00:00Z");
+    assert_eq!(verify(&state, altered).await, vec![VerificationStatus::False; 2]);
+
+    // The endorsement cannot be detached from the proof it references
+    let mut detached = endorsed.clone();
+    detached["signal"]["ngsildproof"]["proof"] = proofs[1].clone();
+    assert_eq!(verify(&state, detached).await, vec![VerificationStatus::False]);
+
+    let mut reordered = endorsed.clone();
+    reordered["signal"]["ngsildproof"]["proof"] = json!([proofs[1], proofs[0]]);
+    assert_eq!(verify(&state, reordered).await, vec![VerificationStatus::False, VerificationStatus::True]);
+
+    let mut tampered = endorsed.clone();
+    tampered["signal"]["value"] = json!(-40);
+    assert_eq!(verify(&state, tampered.clone()).await, vec![VerificationStatus::False; 2]);
+
+    // Signing the changed value drops the stale proofs instead of endorsing them
+    let resealed = sign_as(&state, Cryptosuite::EddsaRdfc2022, tampered).await;
+    let proof = &resealed["signal"]["ngsildproof"]["proof"];
+    assert!(proof.is_object());
+    assert!(proof.get("previousProof").is_none());
+    assert_eq!(verify(&state, resealed).await, vec![VerificationStatus::True]);
+}
+
+#[tokio::test]
+async fn test_verify_notification_and_entity_array() {
+    let (state, _key_store) = test_state("notification");
+
+    let cfg = ConfigRequest {
+        entity_type: "Kiosk".to_string(),
+        properties_to_sign: vec!["status".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let notification = json!({
+        "id": "urn:ngsi-ld:Notification:001",
+        "type": "Notification",
+        "subscriptionId": "urn:ngsi-ld:Subscription:001",
+        "notifiedAt": "2024-01-01T00:00:00Z",
+        "data": [
+            { "id": "urn:ngsi-ld:Kiosk:001", "type": "Kiosk", "status": { "type": "Property", "value": "open" } },
+            { "id": "urn:ngsi-ld:Kiosk:002", "type": "Kiosk", "status": { "type": "Property", "value": "closed" } }
+        ]
+    });
+    let mut signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(notification)).await.unwrap().0;
+    signed["data"][1]["status"]["value"] = json!("open");
+
+    let entities = signed["data"].clone();
+    let single = signed["data"][0].clone();
+
+    for document in [signed, entities] {
+        let request = json!({ "document": document });
+        let results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results["urn:ngsi-ld:Kiosk:001"].get("status").map(|r| r.status), Some(VerificationStatus::True)));
+        assert!(matches!(results["urn:ngsi-ld:Kiosk:002"].get("status").map(|r| r.status), Some(VerificationStatus::False)));
+    }
+
+    let request = json!({ "document": single });
+    let results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+    assert_eq!(results.keys().collect::<Vec<_>>(), vec!["urn:ngsi-ld:Kiosk:001"]);
+}
+
+#[tokio::test]
+async fn test_verification_reports_failure_reasons() {
+    use signer::handlers::verify::FailureReason;
+
+    let (state, _key_store) = test_state("reasons");
+
+    let cfg = ConfigRequest {
+        entity_type: "Pump".to_string(),
+        properties_to_sign: vec!["pressure".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Pump:001",
+            "type": "Pump",
+            "pressure": { "type": "Property", "value": 3.2 },
+            "flow": { "type": "Property", "value": 12 }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+    let proof = signed["pressure"]["ngsildproof"]["proof"].clone();
+
+    let with_proof = |path: &str, value: Value| {
+        let mut document = signed.clone();
+        document["pressure"]["ngsildproof"]["proof"][path] = value;
+        document
+    };
+    let mut tampered = signed.clone();
+    tampered["pressure"]["value"] = json!(9.9);
+    let mut copied = signed.clone();
+    copied["id"] = json!("urn:ngsi-ld:Pump:002");
+    let mut unproven = signed.clone();
+    unproven["pressure"]["ngsildproof"]["proof"].as_object_mut().unwrap().remove("proofValue");
+
+    let expectations = [
+        (signed.clone(), VerificationStatus::True, None),
+        (tampered, VerificationStatus::False, Some(FailureReason::InvalidSignature)),
+        (copied, VerificationStatus::False, Some(FailureReason::EntityMismatch)),
+        (unproven, VerificationStatus::Missing, Some(FailureReason::MissingProofValue)),
+        (with_proof("proofValue", json!("not base64!")), VerificationStatus::False, Some(FailureReason::MalformedProofValue)),
+        (with_proof("proofValue", json!("z3yMApqCuCjXDWPrbjfR5mjCPTHqFG8Pux1TxQrEM35jj")), VerificationStatus::False, Some(FailureReason::InvalidSignatureLength)),
+        (with_proof("cryptosuite", json!("rsa-2018")), VerificationStatus::False, Some(FailureReason::UnknownCryptosuite)),
+        (with_proof("verificationMethod", json!("did:example:unknown#key-1")), VerificationStatus::False, Some(FailureReason::UnknownVerificationMethod)),
+        (with_proof("cryptosuite", json!("ecdsa-rdfc-2019")), VerificationStatus::False, Some(FailureReason::KeyMismatch)),
+    ];
+
+    for (document, status, reason) in expectations {
+        let entity_id = document["id"].as_str().unwrap().to_string();
+        let method = document["pressure"]["ngsildproof"]["proof"]["verificationMethod"].clone();
+        let request = json!({ "document": document });
+        let results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+
+        let result = &results[&entity_id]["pressure"];
+        assert_eq!((result.status, result.reason), (status, reason));
+        assert_eq!(result.verification_method.as_deref(), method.as_str());
+
+        let unsigned = &results[&entity_id]["flow"];
+        assert_eq!((unsigned.status, unsigned.reason), (VerificationStatus::NA, Some(FailureReason::MissingProof)));
+    }
+
+    let results = verify_handler(State(state), Json(serde_json::from_value(json!({ "document": signed })).unwrap())).await.0.results;
+    let result = &results["urn:ngsi-ld:Pump:001"]["pressure"];
+    assert_eq!(result.cryptosuite.as_deref(), proof["cryptosuite"].as_str());
+    assert_eq!(result.created.as_deref(), proof["created"].as_str());
+}
+
+#[tokio::test]
+async fn test_proof_expiry_and_freshness_policy() {
+    use chrono::{Duration, Utc};
+    use signer::handlers::verify::{FailureReason, FreshnessPolicy};
+
+    let (state, _key_store) = test_state("freshness");
+
+    let cfg = ConfigRequest {
+        entity_type: "Gauge".to_string(),
+        properties_to_sign: vec!["level".to_string()],
+        proof_validity_seconds: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Gauge:001",
+            "type": "Gauge",
+            "level": { "type": "Property", "value": 0.4 }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc.clone())).await.unwrap().0["data"][0].clone();
+    let proof = &signed["level"]["ngsildproof"]["proof"];
+    assert_eq!(proof["expires"], proof["created"]);
+
+    let verify = |document: Value, freshness: Value| {
+        let state = state.clone();
+        async move {
+            let request = json!({ "document": document, "freshness": freshness });
+            let results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+            let result = &results["urn:ngsi-ld:Gauge:001"]["level"];
+            (result.status, result.reason)
+        }
+    };
+
+    // Expired proofs keep a valid signature but get a status of their own
+    assert_eq!(verify(signed.clone(), json!({})).await, (VerificationStatus::Expired, Some(FailureReason::Expired)));
+    assert_eq!(verify(signed.clone(), json!({ "clockSkew": 60 })).await, (VerificationStatus::True, None));
+
+    let mut tampered = signed.clone();
+    tampered["level"]["value"] = json!(0.9);
+    assert_eq!(verify(tampered, json!({})).await, (VerificationStatus::False, Some(FailureReason::InvalidSignature)));
+
+    let cfg = ConfigRequest {
+        entity_type: "Gauge".to_string(),
+        properties_to_sign: vec!["level".to_string()],
+        proof_validity_seconds: Some(3600),
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    assert_eq!(verify(signed.clone(), json!({ "maxAge": 60 })).await, (VerificationStatus::True, None));
+    assert_eq!(
+        verify(signed, json!({ "maxAge": 0 })).await,
+        (VerificationStatus::Expired, Some(FailureReason::MaxAgeExceeded))
+    );
+
+    // Proofs from a signer whose clock runs ahead
+    let now = Utc::now();
+    let ahead = json!({ "created": (now + Duration::seconds(30)).to_rfc3339() });
+    let ahead = ahead.as_object().unwrap();
+    let policy = FreshnessPolicy { reject_future: true, ..Default::default() };
+    assert_eq!(policy.check(ahead, now), Err(FailureReason::CreatedInFuture));
+    assert_eq!(FreshnessPolicy { clock_skew: 60, ..policy }.check(ahead, now), Ok(()));
+    assert_eq!(FreshnessPolicy::default().check(ahead, now), Ok(()));
+    assert_eq!(policy.check(json!({ "created": "yesterday" }).as_object().unwrap(), now), Err(FailureReason::MalformedTimestamp));
+
+    let cfg = ConfigRequest {
+        entity_type: "Gauge".to_string(),
+        proof_validity_seconds: Some(u64::MAX),
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 400);
+}
+
+#[tokio::test]
+async fn test_sign_and_verify_sub_attributes() {
+    let (state, _key_store) = test_state("sub-attributes");
+
+    let cfg = ConfigRequest {
+        entity_type: "Hydrant".to_string(),
+        properties_to_sign: vec!["address".to_string(), "address.verifiedBy".to_string(), "/pressure/observedBy".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Hydrant:001",
+            "type": "Hydrant",
+            "address": {
+                "type": "Property",
+                "value": { "streetAddress": "Main Street 1" },
+                "verifiedBy": { "type": "Relationship", "object": "urn:ngsi-ld:Person:001" }
+            },
+            "pressure": {
+                "type": "Property",
+                "value": 4.1,
+                "unitCode": "BAR",
+                "observedBy": { "type": "Relationship", "object": "urn:ngsi-ld:Device:001" },
+                "accuracy": { "type": "Property", "value": 0.1 }
+            }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    assert!(signed["address"]["ngsildproof"].is_object());
+    assert!(signed["address"]["verifiedBy"]["ngsildproof"].is_object());
+    assert!(signed["pressure"]["observedBy"]["ngsildproof"].is_object());
+    assert!(signed["pressure"].get("ngsildproof").is_none());
+
+    let verify = |document: Value| {
+        let state = state.clone();
+        async move {
+            let request = json!({ "document": document });
+            let mut results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+            results.remove("urn:ngsi-ld:Hydrant:001").unwrap().into_iter()
+                .map(|(name, result)| (name, result.status))
+                .collect::<std::collections::HashMap<_, _>>()
+        }
+    };
+
+    let results = verify(signed.clone()).await;
+    assert_eq!(results["address"], VerificationStatus::True);
+    assert_eq!(results["address.verifiedBy"], VerificationStatus::True);
+    assert_eq!(results["pressure"], VerificationStatus::NA);
+    assert_eq!(results["pressure.observedBy"], VerificationStatus::True);
+    assert_eq!(results["pressure.accuracy"], VerificationStatus::NA);
+
+    // The parent proof covers the sub-attributes, but not the other way round
+    let mut tampered = signed.clone();
+    tampered["address"]["verifiedBy"]["object"] = json!("urn:ngsi-ld:Person:002");
+    let results = verify(tampered).await;
+    assert_eq!(results["address"], VerificationStatus::False);
+    assert_eq!(results["address.verifiedBy"], VerificationStatus::False);
+
+    let mut tampered = signed.clone();
+    tampered["address"]["value"]["streetAddress"] = json!("Main Street 2");
+    let results = verify(tampered).await;
+    assert_eq!(results["address"], VerificationStatus::False);
+    assert_eq!(results["address.verifiedBy"], VerificationStatus::True);
+
+    // A sub-attribute proof is bound to its path
+    let mut moved = signed.clone();
+    moved["address"]["observedBy"] = moved["pressure"]["observedBy"].clone();
+    let results = verify(moved).await;
+    assert_eq!(results["address.observedBy"], VerificationStatus::False);
+}
+
+#[tokio::test]
+async fn test_sign_and_verify_multi_attribute_instances() {
+    let (state, _key_store) = test_state("datasets");
+
+    let cfg = ConfigRequest {
+        entity_type: "Tracker".to_string(),
+        properties_to_sign: vec!["speed".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Tracker:001",
+            "type": "Tracker",
+            "speed": [
+                { "type": "Property", "value": 54, "datasetId": "urn:ngsi-ld:Dataset:gps" },
+                { "type": "Property", "value": 56, "datasetId": "urn:ngsi-ld:Dataset:radar" },
+                { "type": "Property", "value": 55 }
+            ]
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    assert_eq!(signed["speed"][0]["ngsildproof"]["datasetIdSealed"], "urn:ngsi-ld:Dataset:gps");
+    assert_eq!(signed["speed"][1]["ngsildproof"]["datasetIdSealed"], "urn:ngsi-ld:Dataset:radar");
+    assert!(signed["speed"][2]["ngsildproof"].get("datasetIdSealed").is_none());
+
+    let verify = |document: Value| {
+        let state = state.clone();
+        async move {
+            let request = json!({ "document": document });
+            let mut results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+            results.remove("urn:ngsi-ld:Tracker:001").unwrap().into_iter()
+                .map(|(name, result)| (name, result.status))
+                .collect::<std::collections::HashMap<_, _>>()
+        }
+    };
+
+    let results = verify(signed.clone()).await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results["speed[urn:ngsi-ld:Dataset:gps]"], VerificationStatus::True);
+    assert_eq!(results["speed[urn:ngsi-ld:Dataset:radar]"], VerificationStatus::True);
+    assert_eq!(results["speed"], VerificationStatus::True);
+
+    // Instances are checked individually
+    let mut tampered = signed.clone();
+    tampered["speed"][1]["value"] = json!(90);
+    let results = verify(tampered).await;
+    assert_eq!(results["speed[urn:ngsi-ld:Dataset:gps]"], VerificationStatus::True);
+    assert_eq!(results["speed[urn:ngsi-ld:Dataset:radar]"], VerificationStatus::False);
+
+    // A proof does not verify on another instance
+    let mut swapped = signed.clone();
+    swapped["speed"][0]["ngsildproof"] = signed["speed"][1]["ngsildproof"].clone();
+    swapped["speed"][2]["ngsildproof"] = signed["speed"][0]["ngsildproof"].clone();
+    let results = verify(swapped).await;
+    assert_eq!(results["speed[urn:ngsi-ld:Dataset:gps]"], VerificationStatus::False);
+    assert_eq!(results["speed"], VerificationStatus::False);
+}
+
+#[tokio::test]
+async fn test_whole_entity_sealing() {
+    use signer::handlers::config::SealingMode;
+
+    let (state, _key_store) = test_state("entity-sealing");
+
+    let configure = |sealing: SealingMode, cryptosuite| ConfigRequest {
+        entity_type: "Turbine".to_string(),
+        properties_to_sign: vec!["power".to_string()],
+        cryptosuite,
+        sealing,
+        ..Default::default()
+    };
+    let status = config_handler(State(state.clone()), Json(configure(SealingMode::Both, signer::cryptosuite::Cryptosuite::Bbs2023))).await;
+    assert_eq!(status.as_u16(), 400);
+    let status = config_handler(State(state.clone()), Json(configure(SealingMode::Entity, Default::default()))).await;
+    assert_eq!(status.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Turbine:001",
+            "type": "Turbine",
+            "scope": "/Europe/Spain",
+            "power": { "type": "Property", "value": 1.5, "unitCode": "MAW" },
+            "status": { "type": "Property", "value": "running" }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc.clone())).await.unwrap().0["data"][0].clone();
+    assert!(signed["ngsildproof"]["proof"].is_object());
+    assert!(signed["power"].get("ngsildproof").is_none());
+
+    let verify = |document: Value| {
+        let state = state.clone();
+        async move {
+            let request = json!({ "document": document });
+            let mut results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+            results.remove("urn:ngsi-ld:Turbine:001").unwrap().into_iter()
+                .map(|(name, result)| (name, result.status))
+                .collect::<std::collections::HashMap<_, _>>()
+        }
+    };
+
+    assert_eq!(verify(signed.clone()).await["ngsildproof"], VerificationStatus::True);
+
+    // Adding, removing or altering anything breaks the seal
+    let mut added = signed.clone();
+    added["maintenance"] = json!({ "type": "Property", "value": "due" });
+    let mut removed = signed.clone();
+    removed.as_object_mut().unwrap().remove("status");
+    let mut rescoped = signed.clone();
+    rescoped["scope"] = json!("/Europe/France");
+    for document in [added, removed, rescoped] {
+        assert_eq!(verify(document).await["ngsildproof"], VerificationStatus::False);
+    }
+
+    // Attribute proofs can be combined with the entity seal, in any order
+    assert_eq!(config_handler(State(state.clone()), Json(configure(SealingMode::Both, Default::default()))).await.as_u16(), 200);
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+    let results = verify(signed.clone()).await;
+    assert_eq!(results["ngsildproof"], VerificationStatus::True);
+    assert_eq!(results["power"], VerificationStatus::True);
+    assert_eq!(results["status"], VerificationStatus::NA);
+
+    let mut tampered = signed.clone();
+    tampered["power"]["value"] = json!(2.0);
+    let results = verify(tampered).await;
+    assert_eq!(results["ngsildproof"], VerificationStatus::False);
+    assert_eq!(results["power"], VerificationStatus::False);
+}
+
+#[tokio::test]
+async fn test_missing_signed_attributes_are_reported() {
+    use signer::handlers::config::SealingMode;
+    use signer::handlers::verify::FailureReason;
+
+    let (state, _key_store) = test_state("missing");
+
+    let cfg = ConfigRequest {
+        entity_type: "Elevator".to_string(),
+        properties_to_sign: vec!["floor".to_string(), "load".to_string(), "door.lockedBy".to_string()],
+        sealing: SealingMode::Both,
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Elevator:001",
+            "type": "Elevator",
+            "floor": { "type": "Property", "value": 3 },
+            "load": [
+                { "type": "Property", "value": 420, "datasetId": "urn:ngsi-ld:Dataset:cell" },
+                { "type": "Property", "value": 415, "datasetId": "urn:ngsi-ld:Dataset:door" }
+            ],
+            "door": {
+                "type": "Property",
+                "value": "closed",
+                "lockedBy": { "type": "Relationship", "object": "urn:ngsi-ld:Controller:001" }
+            }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    let verify = |document: Value| {
+        let state = state.clone();
+        async move {
+            let request = json!({ "document": document });
+            let mut results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+            results.remove("urn:ngsi-ld:Elevator:001").unwrap().into_iter()
+                .map(|(name, result)| (name, (result.status, result.reason)))
+                .collect::<std::collections::HashMap<_, _>>()
+        }
+    };
+
+    let results = verify(signed.clone()).await;
+    assert_eq!(results["door"], (VerificationStatus::NA, Some(FailureReason::MissingProof)));
+    assert!(results.iter().filter(|(name, _)| *name != "door").all(|(_, (status, _))| *status == VerificationStatus::True));
+
+    let mut stripped = signed.clone();
+    let entity = stripped.as_object_mut().unwrap();
+    entity.remove("floor");
+    entity.remove("ngsildproof");
+    entity["load"][1].as_object_mut().unwrap().remove("ngsildproof");
+    entity["door"]["lockedBy"].as_object_mut().unwrap().remove("ngsildproof");
+
+    let results = verify(stripped).await;
+    assert_eq!(results["floor"], (VerificationStatus::Missing, Some(FailureReason::AttributeMissing)));
+    assert_eq!(results["ngsildproof"], (VerificationStatus::Missing, Some(FailureReason::MissingProof)));
+    assert_eq!(results["load[urn:ngsi-ld:Dataset:cell]"], (VerificationStatus::True, None));
+    assert_eq!(results["load[urn:ngsi-ld:Dataset:door]"], (VerificationStatus::Missing, Some(FailureReason::MissingProof)));
+    assert_eq!(results["door.lockedBy"], (VerificationStatus::Missing, Some(FailureReason::MissingProof)));
+}
+
+#[tokio::test]
+async fn test_sign_concise_and_key_values_entities() {
+    use signer::representation::Representation;
+
+    let (state, _key_store) = test_state("representation");
+
+    let cfg = ConfigRequest {
+        entity_type: "Kettle".to_string(),
+        properties_to_sign: vec!["temperature".to_string(), "owner".to_string(), "location".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let concise = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Kettle:001",
+            "type": "Kettle",
+            "temperature": 92,
+            "owner": { "object": "urn:ngsi-ld:Person:001" },
+            "location": { "type": "Point", "coordinates": [2.18, 41.38] }
+        }]
+    });
+    let key_values = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Kettle:001",
+            "type": "Kettle",
+            "temperature": 92,
+            "owner": "urn:ngsi-ld:Person:001",
+            "location": { "type": "Point", "coordinates": [2.18, 41.38] }
+        }]
+    });
+
+    let verify = |document: Value| {
+        let state = state.clone();
+        async move {
+            let request = json!({ "document": document });
+            let results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+            results["urn:ngsi-ld:Kettle:001"].values().all(|result| result.status == VerificationStatus::True)
+        }
+    };
+
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(concise.clone())).await.unwrap().0["data"][0].clone();
+    assert_eq!(signed["temperature"]["value"], 92);
+    assert!(signed["temperature"].get("type").is_none());
+    assert!(signed["temperature"]["ngsildproof"].is_object());
+    assert!(signed["owner"].get("type").is_none());
+    assert_eq!(signed["location"]["value"]["type"], "Point");
+    assert!(verify(signed).await);
+
+    // keyValues cannot carry proofs, so the entity comes back concise
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(key_values)).await.unwrap().0["data"][0].clone();
+    assert_eq!(signed["temperature"]["value"], 92);
+    assert!(signed["location"]["ngsildproof"].is_object());
+    assert!(verify(signed).await);
+
+    let query = SignQuery { options: Some(Representation::Normalized) };
+    let signed = sign_handler(State(state.clone()), Query(query), Json(concise)).await.unwrap().0["data"][0].clone();
+    assert_eq!(signed["temperature"]["type"], "Property");
+    assert_eq!(signed["owner"]["type"], "Relationship");
+    assert_eq!(signed["location"]["type"], "GeoProperty");
+    assert!(verify(signed).await);
+}
+
+#[tokio::test]
+async fn test_sign_keeps_ngsi_ld_attribute_members() {
+    use signer::representation::{concise, normalize, Representation};
+
+    let (state, _key_store) = test_state("attribute-members");
+
+    let cfg = ConfigRequest {
+        entity_type: "Crane".to_string(),
+        properties_to_sign: vec![],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let entity = json!({
+        "id": "urn:ngsi-ld:Crane:001",
+        "type": "Crane",
+        "load": {
+            "type": "Property",
+            "value": 12,
+            "previousValue": 10,
+            "unitCode": "TNE",
+            "observedAt": "2024-05-01T10:00:00Z"
+        },
+        "name": { "type": "Property", "value": "Grue", "lang": "fr" },
+        "operator": {
+            "type": "Relationship",
+            "object": "urn:ngsi-ld:Person:002",
+            "objectType": "Person",
+            "previousObject": "urn:ngsi-ld:Person:001",
+            "entity": { "id": "urn:ngsi-ld:Person:002", "type": "Person" }
+        },
+        "crew": {
+            "type": "ListRelationship",
+            "objectList": [{ "object": "urn:ngsi-ld:Person:002" }],
+            "entityList": [{ "id": "urn:ngsi-ld:Person:002", "type": "Person" }]
+        },
+        "label": {
+            "type": "LanguageProperty",
+            "languageMap": { "en": "Tower crane" },
+            "previousLanguageMap": { "en": "Crane" }
+        },
+        "category": { "type": "VocabProperty", "vocab": "Tower", "previousVocab": "Mobile" },
+        "settings": { "type": "JsonProperty", "json": { "rate": 5 }, "previousJson": { "rate": 4 } }
+    });
+
+    let attributes = entity.as_object().unwrap();
+    assert_eq!(&normalize(&concise(attributes)), attributes);
+
+    let request = json!({ "data": [entity.clone()] });
+    let query = SignQuery { options: Some(Representation::Normalized) };
+    let signed = sign_handler(State(state.clone()), Query(query), Json(request)).await.unwrap().0["data"][0].clone();
+
+    for (name, attribute) in attributes.iter().filter(|(name, _)| !["id", "type"].contains(&name.as_str())) {
+        let mut unsigned = signed[name].clone();
+        assert!(unsigned.as_object_mut().unwrap().remove("ngsildproof").is_some(), "{} was not signed", name);
+        assert_eq!(&unsigned, attribute, "{} was not kept as sent", name);
+    }
+
+    let request = json!({ "document": signed });
+    let results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+    let results = &results["urn:ngsi-ld:Crane:001"];
+    assert_eq!(results.len(), 7);
+    assert!(results.values().all(|result| result.status == VerificationStatus::True));
+}
+
+#[tokio::test]
+async fn test_sign_attributes_selected_by_type() {
+    use signer::attribute::AttributeType;
+
+    let (state, _key_store) = test_state("attribute-types");
+
+    let cfg = ConfigRequest {
+        entity_type: "Drone".to_string(),
+        attribute_types: vec![AttributeType::Relationship, AttributeType::GeoProperty, AttributeType::LanguageProperty],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Drone:002",
+            "type": "Drone",
+            "speed": { "type": "Property", "value": 12 },
+            "operator": { "type": "Relationship", "object": "urn:ngsi-ld:Company:001" },
+            "location": { "type": "GeoProperty", "value": { "type": "Point", "coordinates": [2.18, 41.38] } },
+            "name": { "type": "LanguageProperty", "languageMap": { "en-GB": "Drone" } }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+    assert!(signed["speed"].get("ngsildproof").is_none());
+
+    let verify = |document: Value| {
+        let state = state.clone();
+        async move {
+            let request = json!({ "document": document });
+            let mut results = verify_handler(State(state), Json(serde_json::from_value(request).unwrap())).await.0.results;
+            results.remove("urn:ngsi-ld:Drone:002").unwrap().into_iter()
+                .map(|(name, result)| (name, result.status))
+                .collect::<std::collections::HashMap<_, _>>()
+        }
+    };
+
+    let results = verify(signed.clone()).await;
+    assert_eq!(results["speed"], VerificationStatus::NA);
+    for name in ["operator", "location", "name"] {
+        assert_eq!(results[name], VerificationStatus::True);
+    }
+
+    // Equivalent values written differently keep their proofs
+    let mut rewritten = signed.clone();
+    rewritten["location"]["value"]["coordinates"] = json!([2.18000001, 41.38]);
+    rewritten["name"]["languageMap"] = json!({ "EN-gb": "Drone" });
+    let results = verify(rewritten).await;
+    for name in ["location", "name"] {
+        assert_eq!(results[name], VerificationStatus::True);
+    }
+
+    // Entity ids are compared as exact strings: another case is another entity
+    let mut moved = signed;
+    moved["operator"]["object"] = json!("urn:NGSI-LD:Company:001");
+    moved["location"]["value"]["coordinates"] = json!([2.19, 41.38]);
+    let results = verify(moved).await;
+    assert_eq!(results["operator"], VerificationStatus::False);
+    assert_eq!(results["location"], VerificationStatus::False);
+}
+
+#[tokio::test]
+async fn test_batch_sign_reports_each_entity() {
+    let (state, _key_store) = test_state("batch");
+
+    let cfg = ConfigRequest {
+        entity_type: "Boiler".to_string(),
+        properties_to_sign: vec!["pressure".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [
+            { "id": "urn:ngsi-ld:Boiler:001", "type": "Boiler", "pressure": { "type": "Property", "value": 1.8 } },
+            { "id": "urn:ngsi-ld:Unconfigured:001", "type": "Unconfigured", "pressure": { "type": "Property", "value": 2.1 } },
+            { "id": "urn:ngsi-ld:Boiler:002", "type": "Boiler", "temperature": { "type": "Property", "value": 70 } }
+        ]
+    });
+
+    // The whole notification is rejected by /sign
+    let rejected = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc.clone())).await;
+    assert_eq!(rejected.unwrap_err().status(), axum::http::StatusCode::PRECONDITION_REQUIRED);
+
+    let (status, result) = sign_batch_handler(State(state.clone()), Query(SignQuery::default()), Json(doc.clone())).await.unwrap();
+    assert_eq!(status, axum::http::StatusCode::MULTI_STATUS);
+
+    let statuses: Vec<_> = result.results.iter().map(|r| (r.entity_id.as_str(), r.status)).collect();
+    assert_eq!(statuses, vec![
+        ("urn:ngsi-ld:Boiler:001", EntityStatus::Signed),
+        ("urn:ngsi-ld:Unconfigured:001", EntityStatus::Error),
+        ("urn:ngsi-ld:Boiler:002", EntityStatus::Skipped),
+    ]);
+    assert!(result.results[1].reason.as_deref().unwrap().contains("Unconfigured"));
+
+    let data = &result.document["data"];
+    assert!(data[0]["pressure"]["ngsildproof"].is_object());
+    assert_eq!(data[1], doc["data"][1]);
+    assert_eq!(data[2], doc["data"][2]);
+
+    let request = json!({ "document": data[0].clone() });
+    let results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+    assert_eq!(results["urn:ngsi-ld:Boiler:001"]["pressure"].status, VerificationStatus::True);
+
+    let signed_only = json!({ "data": [doc["data"][0].clone()] });
+    let (status, _) = sign_batch_handler(State(state), Query(SignQuery::default()), Json(signed_only)).await.unwrap();
+    assert_eq!(status, axum::http::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_batch_reports_already_signed_entity_unchanged() {
+    let (state, _key_store) = test_state("batch-unchanged");
+
+    let cfg = ConfigRequest {
+        entity_type: "Furnace".to_string(),
+        properties_to_sign: vec!["temperature".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{ "id": "urn:ngsi-ld:Furnace:001", "type": "Furnace", "temperature": { "type": "Property", "value": 850 } }]
+    });
+    let (_, result) = sign_batch_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap();
+    assert_eq!(result.results[0].status, EntityStatus::Signed);
+
+    // The notification of the service's own update is not signed again
+    let resubmitted = result.0.document;
+    let (status, result) = sign_batch_handler(State(state), Query(SignQuery::default()), Json(resubmitted.clone())).await.unwrap();
+    assert_eq!(status, axum::http::StatusCode::OK);
+    assert_eq!(result.results[0].status, EntityStatus::Unchanged);
+    assert_eq!(result.document, resubmitted);
+}
+
+#[tokio::test]
+async fn test_resigning_keeps_valid_proofs() {
+    let (state, _key_store) = test_state("resign");
+
+    let cfg = ConfigRequest {
+        entity_type: "Heater".to_string(),
+        properties_to_sign: vec!["power".to_string(), "mode".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let sign = |entity: Value| {
+        let state = state.clone();
+        async move {
+            let doc = json!({ "data": [entity] });
+            sign_handler(State(state), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone()
+        }
+    };
+
+    let signed = sign(json!({
+        "id": "urn:ngsi-ld:Heater:001",
+        "type": "Heater",
+        "power": { "type": "Property", "value": 1500 },
+        "mode": { "type": "Property", "value": "eco" }
+    })).await;
+
+    // The notification of the service's own update comes back unchanged
+    assert_eq!(sign(signed.clone()).await, signed);
+
+    // Only the changed attribute is signed again
+    let mut changed = signed.clone();
+    changed["power"]["value"] = json!(2000);
+    let resigned = sign(changed).await;
+    assert_eq!(resigned["mode"], signed["mode"]);
+    assert_ne!(resigned["power"]["ngsildproof"]["proof"]["id"], signed["power"]["ngsildproof"]["proof"]["id"]);
+
+    let request = json!({ "document": resigned });
+    let results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+    assert!(results["urn:ngsi-ld:Heater:001"].values().all(|result| result.status == VerificationStatus::True));
+}
+
+#[tokio::test]
+async fn test_proof_from_unknown_did_key_is_rejected() {
+    use ed25519_dalek::{Signer, SigningKey};
+    use signer::cryptosuite::Cryptosuite;
+    use signer::handlers::verify::FailureReason;
+    use signer::jsonld::context::ContextLoader;
+    use signer::keystore::KeyAlgorithm;
+    use signer::{multikey, proof};
+
+    let (state, _key_store) = test_state("did-key");
+
+    // Anyone can sign an attribute with a did:key of their own
+    let signing_key = SigningKey::from_bytes(&[42u8; 32]);
+    let multibase = multikey::encode_ed25519(&signing_key.verifying_key());
+    let attribute = json!({ "type": "Property", "value": 13 });
+
+    let document = proof::attribute_document(
+        "urn:ngsi-ld:Locker:001",
+        "Locker",
+        &["code".to_string()],
+        &proof::sealed_content(attribute.as_object().unwrap()).unwrap(),
+    );
+    let mut options = json!({
+        "type": "DataIntegrityProof",
+        "created": "2025-01-01T00:00:00Z",
+        "verificationMethod": format!("did:key:{}#{}", multibase, multibase),
+        "cryptosuite": "eddsa-jcs-2022",
+        "proofPurpose": "assertionMethod"
+    });
+    let to_sign = proof::signing_input(Cryptosuite::EddsaJcs2022, KeyAlgorithm::Ed25519, &document, &options, &ContextLoader::new()).unwrap();
+    options["proofValue"] = json!(proof::encode_proof_value(&signing_key.sign(&to_sign).to_bytes()));
+
+    let mut signed_attribute = attribute.clone();
+    signed_attribute["ngsildproof"] = json!({
+        "type": "Property",
+        "entityIdSealed": "urn:ngsi-ld:Locker:001",
+        "entityTypeSealed": "Locker",
+        "proof": options
+    });
+    let entity = json!({ "id": "urn:ngsi-ld:Locker:001", "type": "Locker", "code": signed_attribute });
+
+    let request = json!({ "document": entity });
+    let results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+    let result = &results["urn:ngsi-ld:Locker:001"]["code"];
+    assert_eq!(result.status, VerificationStatus::False);
+    assert_eq!(result.reason, Some(FailureReason::UnknownVerificationMethod));
+
+    // The same proof holds once the DID is trusted
+    let trusting = state.with_resolver(signer::resolver::DidKeyResolver::new([format!("did:key:{}", multibase)]));
+    let request = json!({ "document": entity });
+    let results = verify_handler(State(trusting), Json(serde_json::from_value(request).unwrap())).await.0.results;
+    assert_eq!(results["urn:ngsi-ld:Locker:001"]["code"].status, VerificationStatus::True);
+}
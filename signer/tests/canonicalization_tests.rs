@@ -0,0 +1,283 @@
+use serde_json::json;
+use signer::jsonld::{self, JsonLdError};
+use signer::jsonld::context::{ContextLoader, NGSI_LD_CORE_CONTEXT};
+
+mod common;
+
+use common::TempPath;
+
+#[test]
+fn test_canonical_nquads_of_ngsi_ld_attribute() {
+    let document = json!({
+        "@context": NGSI_LD_CORE_CONTEXT,
+        "id": "urn:ngsi-ld:Sensor:001",
+        "type": "Sensor",
+        "temperature": { "type": "Property", "value": 21.5 }
+    });
+
+    let canonical = jsonld::canonize(&document, &ContextLoader::new()).unwrap();
+
+    let expected = concat!(
+        "<urn:ngsi-ld:Sensor:001> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://uri.etsi.org/ngsi-ld/default-context/Sensor> .\n",
+        "<urn:ngsi-ld:Sensor:001> <https://uri.etsi.org/ngsi-ld/default-context/temperature> _:c14n0 .\n",
+        "_:c14n0 <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://uri.etsi.org/ngsi-ld/Property> .\n",
+        "_:c14n0 <https://uri.etsi.org/ngsi-ld/hasValue> \"2.15E1\"^^<http://www.w3.org/2001/XMLSchema#double> .\n",
+    );
+    assert_eq!(canonical, expected);
+}
+
+#[test]
+fn test_canonicalization_ignores_blank_node_labels_and_order() {
+    let context = json!({ "@vocab": "https://example.org/" });
+
+    let first = json!([
+        { "@context": context, "@id": "_:x", "knows": { "@id": "_:y" } },
+        { "@context": context, "@id": "_:y", "knows": { "@id": "_:x" } }
+    ]);
+    let second = json!([
+        { "@context": context, "@id": "_:b", "knows": { "@id": "_:a" } },
+        { "@context": context, "@id": "_:a", "knows": { "@id": "_:b" } }
+    ]);
+
+    let loader = ContextLoader::new();
+    let canonical = jsonld::canonize(&first, &loader).unwrap();
+
+    assert_eq!(canonical, jsonld::canonize(&second, &loader).unwrap());
+    assert_eq!(canonical, concat!(
+        "_:c14n0 <https://example.org/knows> _:c14n1 .\n",
+        "_:c14n1 <https://example.org/knows> _:c14n0 .\n",
+    ));
+}
+
+#[test]
+fn test_unmappable_terms_fail_canonicalization() {
+    let loader = ContextLoader::new();
+    let context = json!({ "temperature": "https://example.org/temperature" });
+
+    // Without @vocab, a member the context does not define cannot be signed
+    let undefined = json!({
+        "@context": context,
+        "@id": "urn:ngsi-ld:Sensor:002",
+        "temperature": 21,
+        "humidity": 40
+    });
+    assert!(matches!(jsonld::canonize(&undefined, &loader), Err(JsonLdError::InvalidDocument(_))));
+
+    let relative_id = json!({ "@context": context, "@id": "sensor-2", "temperature": 21 });
+    assert!(matches!(jsonld::canonize(&relative_id, &loader), Err(JsonLdError::InvalidDocument(_))));
+
+    let relative_type = json!({ "@context": context, "@id": "urn:ngsi-ld:Sensor:002", "@type": "Sensor", "temperature": 21 });
+    assert!(matches!(jsonld::canonize(&relative_type, &loader), Err(JsonLdError::InvalidDocument(_))));
+
+    // A term mapped to null is excluded on purpose
+    let excluded = json!({
+        "@context": [context, { "humidity": null }],
+        "@id": "urn:ngsi-ld:Sensor:002",
+        "temperature": 21,
+        "humidity": 40
+    });
+    assert_eq!(
+        jsonld::canonize(&excluded, &loader).unwrap(),
+        "<urn:ngsi-ld:Sensor:002> <https://example.org/temperature> \"21\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n"
+    );
+
+    // The complete core context maps every NGSI-LD member
+    let document = json!({
+        "@context": NGSI_LD_CORE_CONTEXT,
+        "id": "urn:ngsi-ld:Sensor:002",
+        "type": "Sensor",
+        "settings": { "type": "JsonProperty", "json": { "rate": 5 } },
+        "deleted": { "type": "Property", "value": 1, "deletedAt": "2024-01-01T00:00:00Z" }
+    });
+    let canonical = jsonld::canonize(&document, &loader).unwrap();
+    assert!(canonical.contains("<https://uri.etsi.org/ngsi-ld/hasJSON> \"{\\\"rate\\\":5}\"^^<http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON>"));
+    assert!(canonical.contains("<https://uri.etsi.org/ngsi-ld/deletedAt> \"2024-01-01T00:00:00Z\"^^<https://uri.etsi.org/ngsi-ld/DateTime>"));
+}
+
+#[test]
+fn test_remote_contexts_are_only_read_from_the_local_cache() {
+    let document = json!({
+        "@context": "https://example.org/contexts/store.jsonld",
+        "id": "urn:ngsi-ld:Store:001",
+        "type": "Store"
+    });
+
+    let result = jsonld::canonize(&document, &ContextLoader::new());
+    assert!(matches!(result, Err(JsonLdError::UnknownContext(_))));
+
+    let dir = TempPath::dir("contexts");
+    std::fs::create_dir_all(dir.join("example.org/contexts")).unwrap();
+    std::fs::write(
+        dir.join("example.org/contexts/store.jsonld"),
+        r#"{ "@context": { "id": "@id", "type": "@type", "Store": "https://schema.org/Store" } }"#,
+    ).unwrap();
+
+    let canonical = jsonld::canonize(&document, &ContextLoader::new().with_dir(&*dir)).unwrap();
+    assert_eq!(
+        canonical,
+        "<urn:ngsi-ld:Store:001> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://schema.org/Store> .\n"
+    );
+}
+
+#[test]
+fn test_attribute_values_are_canonicalized_by_type() {
+    use signer::attribute::canonicalize;
+
+    let entity = json!({
+        "id": "urn:ngsi-ld:Drone:001",
+        "type": "Drone",
+        "location": { "type": "GeoProperty", "value": { "type": "Point", "coordinates": [2.18000004, 41.38, 12] } },
+        "name": { "type": "LanguageProperty", "languageMap": { "EN-gb": "Drone", "zh-hant-tw": "無人機", "@none": "Drone" } },
+        "operator": { "type": "Relationship", "object": "urn:NGSI-LD:Company:001" },
+        "route": { "type": "ListRelationship", "objectList": [{ "object": "HTTPS://example.org/Waypoint/1" }] },
+        "category": { "type": "VocabProperty", "vocab": "ex:Quadcopter" },
+        "payloads": { "type": "ListProperty", "valueList": "camera" },
+        "speed": { "type": "Property", "value": 12.123456789 }
+    });
+
+    let canonical = canonicalize(&entity).unwrap();
+
+    assert_eq!(canonical["location"]["value"]["coordinates"], json!([2.18, 41.38, 12]));
+    assert_eq!(canonical["name"]["languageMap"], json!({ "en-GB": "Drone", "zh-Hant-TW": "無人機", "@none": "Drone" }));
+    assert_eq!(canonical["operator"], entity["operator"]);
+    assert_eq!(canonical["route"], entity["route"]);
+    assert_eq!(canonical["category"]["vocab"], "ex:Quadcopter");
+    assert_eq!(canonical["payloads"]["valueList"], json!(["camera"]));
+    assert_eq!(canonical["speed"], entity["speed"]);
+    assert_eq!(canonicalize(&canonical).unwrap(), canonical);
+
+    let schemed = json!({ "type": "VocabProperty", "vocab": "HTTPS://example.org/Quadcopter" });
+    assert_eq!(canonicalize(&schemed).unwrap()["vocab"], "https://example.org/Quadcopter");
+
+    // Tags that only differ by case would seal two texts under one tag
+    let colliding = json!({ "type": "LanguageProperty", "languageMap": { "en-GB": "Drone", "en-gb": "UAV" } });
+    assert_eq!(
+        canonicalize(&colliding),
+        Err(signer::attribute::AttributeError::DuplicateLanguageTag("en-GB".into()))
+    );
+}
+
+/// RDF Dataset Canonicalization (RDFC-1.0), the "unique hashes" and "shared
+/// hashes" examples, given in expanded JSON-LD.
+#[test]
+fn test_rdfc_1_0_examples() {
+    let loader = ContextLoader::new();
+
+    let unique_hashes = json!([
+        {
+            "@id": "http://example.com/#p",
+            "http://example.com/#q": { "@id": "_:e0" },
+            "http://example.com/#r": { "@id": "_:e1" }
+        },
+        { "@id": "_:e0", "http://example.com/#s": { "@id": "http://example.com/#u" } },
+        { "@id": "_:e1", "http://example.com/#t": { "@id": "http://example.com/#u" } }
+    ]);
+    assert_eq!(jsonld::canonize(&unique_hashes, &loader).unwrap(), concat!(
+        "<http://example.com/#p> <http://example.com/#q> _:c14n0 .\n",
+        "<http://example.com/#p> <http://example.com/#r> _:c14n1 .\n",
+        "_:c14n0 <http://example.com/#s> <http://example.com/#u> .\n",
+        "_:c14n1 <http://example.com/#t> <http://example.com/#u> .\n",
+    ));
+
+    let shared_hashes = json!([
+        {
+            "@id": "http://example.com/#p",
+            "http://example.com/#q": [{ "@id": "_:e0" }, { "@id": "_:e1" }]
+        },
+        { "@id": "_:e0", "http://example.com/#p": { "@id": "_:e2" } },
+        { "@id": "_:e1", "http://example.com/#p": { "@id": "_:e3" } },
+        { "@id": "_:e2", "http://example.com/#r": { "@id": "_:e3" } }
+    ]);
+    assert_eq!(jsonld::canonize(&shared_hashes, &loader).unwrap(), concat!(
+        "<http://example.com/#p> <http://example.com/#q> _:c14n2 .\n",
+        "<http://example.com/#p> <http://example.com/#q> _:c14n3 .\n",
+        "_:c14n0 <http://example.com/#r> _:c14n1 .\n",
+        "_:c14n2 <http://example.com/#p> _:c14n1 .\n",
+        "_:c14n3 <http://example.com/#p> _:c14n0 .\n",
+    ));
+}
+
+/// Data Integrity EdDSA Cryptosuites v1.0, appendix "Representation:
+/// eddsa-rdfc-2022". The credential contexts are not bundled: the cache holds
+/// the terms of `https://www.w3.org/ns/credentials/v2` the credential uses,
+/// and its proof vocabulary, which is that of the Data Integrity context.
+#[test]
+fn test_eddsa_rdfc_2022_test_vector() {
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::Value;
+    use signer::cryptosuite::Cryptosuite;
+    use signer::jsonld::rdfc::sha256_hex;
+    use signer::keystore::KeyAlgorithm;
+    use signer::proof;
+
+    let mut credentials: Value = serde_json::from_str(include_str!("../contexts/data-integrity-v2.jsonld")).unwrap();
+    let terms = credentials["@context"].as_object_mut().unwrap();
+    terms.insert("VerifiableCredential".into(), json!("https://www.w3.org/2018/credentials#VerifiableCredential"));
+    terms.insert("name".into(), json!("https://schema.org/name"));
+    terms.insert("description".into(), json!("https://schema.org/description"));
+    terms.insert("issuer".into(), json!({ "@id": "https://www.w3.org/2018/credentials#issuer", "@type": "@id" }));
+    terms.insert("validFrom".into(), json!({
+        "@id": "https://www.w3.org/2018/credentials#validFrom",
+        "@type": "http://www.w3.org/2001/XMLSchema#dateTime"
+    }));
+    terms.insert("credentialSubject".into(), json!({
+        "@id": "https://www.w3.org/2018/credentials#credentialSubject",
+        "@type": "@id"
+    }));
+
+    let dir = TempPath::dir("credential-contexts");
+    std::fs::create_dir_all(dir.join("www.w3.org/ns/credentials/examples")).unwrap();
+    std::fs::write(dir.join("www.w3.org/ns/credentials/v2"), credentials.to_string()).unwrap();
+    std::fs::write(
+        dir.join("www.w3.org/ns/credentials/examples/v2"),
+        r#"{ "@context": { "@vocab": "https://www.w3.org/ns/credentials/examples#" } }"#,
+    ).unwrap();
+    let loader = ContextLoader::new().with_dir(&*dir);
+
+    let context = json!(["https://www.w3.org/ns/credentials/v2", "https://www.w3.org/ns/credentials/examples/v2"]);
+    let credential = json!({
+        "@context": context,
+        "id": "urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33",
+        "type": ["VerifiableCredential", "AlumniCredential"],
+        "name": "Alumni Credential",
+        "description": "A minimum viable example of an Alumni Credential.",
+        "issuer": "https://vc.example/issuers/5678",
+        "validFrom": "2023-01-01T00:00:00Z",
+        "credentialSubject": {
+            "id": "did:example:abcdefgh",
+            "alumniOf": "The School of Examples"
+        }
+    });
+    let proof_config = json!({
+        "type": "DataIntegrityProof",
+        "cryptosuite": "eddsa-rdfc-2022",
+        "created": "2023-02-24T23:36:38Z",
+        "verificationMethod": "did:key:z6MkrJVnaZkeFzdQyMZu1cgjg7k1pZZ6pvBQ7XJPt4swbTQ2#z6MkrJVnaZkeFzdQyMZu1cgjg7k1pZZ6pvBQ7XJPt4swbTQ2",
+        "proofPurpose": "assertionMethod",
+        "@context": context
+    });
+
+    let canonical_credential = jsonld::canonize(&credential, &loader).unwrap();
+    assert_eq!(canonical_credential, concat!(
+        "<did:example:abcdefgh> <https://www.w3.org/ns/credentials/examples#alumniOf> \"The School of Examples\" .\n",
+        "<urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://www.w3.org/2018/credentials#VerifiableCredential> .\n",
+        "<urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://www.w3.org/ns/credentials/examples#AlumniCredential> .\n",
+        "<urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33> <https://schema.org/description> \"A minimum viable example of an Alumni Credential.\" .\n",
+        "<urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33> <https://schema.org/name> \"Alumni Credential\" .\n",
+        "<urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33> <https://www.w3.org/2018/credentials#credentialSubject> <did:example:abcdefgh> .\n",
+        "<urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33> <https://www.w3.org/2018/credentials#issuer> <https://vc.example/issuers/5678> .\n",
+        "<urn:uuid:58172aac-d8ba-11ed-83dd-0b3aef56cc33> <https://www.w3.org/2018/credentials#validFrom> \"2023-01-01T00:00:00Z\"^^<http://www.w3.org/2001/XMLSchema#dateTime> .\n",
+    ));
+    assert_eq!(sha256_hex(canonical_credential.as_bytes()), "517744132ae165a5349155bef0bb0cf2258fff99dfe1dbd914b938d775a36017");
+
+    let canonical_config = jsonld::canonize(&proof_config, &loader).unwrap();
+    assert_eq!(sha256_hex(canonical_config.as_bytes()), "bea7b7acfbad0126b135104024a5f1733e705108f42d59668b05c0c50004c6b0");
+
+    let hash_data = proof::signing_input(Cryptosuite::EddsaRdfc2022, KeyAlgorithm::Ed25519, &credential, &proof_config, &loader).unwrap();
+    let secret_key = bs58::decode("3u2en7t5LR2WtQH5PfFqMqwVHBeXouLzo6haApm8XHqvjxq").into_vec().unwrap();
+    let signing_key = SigningKey::from_bytes(secret_key[2..].try_into().unwrap());
+    assert_eq!(
+        proof::encode_proof_value(&signing_key.sign(&hash_data).to_bytes()),
+        "z2YwC8z3ap7yx1nZYCg4L3j3ApHsF8kgPdSb5xoS1VR7vPG3F561B52hYnQF9iseabecm3ijx4K1FBTQsCZahKZme"
+    );
+}
@@ -0,0 +1,66 @@
+//! Fixtures shared by the integration tests.
+
+// Each test crate only uses some of the fixtures
+#![allow(dead_code)]
+
+use axum::Json;
+use axum::extract::State;
+use serde_json::{json, Value};
+use signer::handlers::verify::{verify_handler, VerificationStatus};
+use signer::keystore::KeyStore;
+use signer::state::AppState;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A file or directory of the system temp directory, removed when dropped.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    /// `signer-tests-<pid>-<name>`, not created yet.
+    pub fn new(name: &str) -> Self {
+        TempPath(std::env::temp_dir().join(format!("signer-tests-{}-{}", std::process::id(), name)))
+    }
+
+    /// A new empty directory.
+    pub fn dir(name: &str) -> Self {
+        let dir = TempPath::new(name);
+        std::fs::create_dir_all(&dir.0).unwrap();
+        dir
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = match self.0.is_dir() {
+            true => std::fs::remove_dir_all(&self.0),
+            false => std::fs::remove_file(&self.0),
+        };
+    }
+}
+
+/// A service over a new key store, which lives as long as the returned path.
+pub fn test_state(name: &str) -> (AppState, TempPath) {
+    let key_store = TempPath::new(&format!("{}.json", name));
+    let state = AppState::new(KeyStore::load_or_generate(&*key_store).unwrap());
+    (state, key_store)
+}
+
+/// Verifies `document` and returns the status of each of its attributes.
+pub async fn verify(state: &AppState, document: Value) -> HashMap<String, VerificationStatus> {
+    let entity_id = document["id"].as_str().unwrap_or_default().to_string();
+    let request = json!({ "document": document });
+    let mut results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+    results.remove(&entity_id).unwrap_or_default()
+        .into_iter()
+        .map(|(name, result)| (name, result.status))
+        .collect()
+}
@@ -0,0 +1,187 @@
+use axum::Json;
+use axum::extract::{Query, State};
+use serde_json::{json};
+use signer::handlers::{config::config_handler, sign::{sign_handler, SignQuery}};
+use signer::handlers::config::ConfigRequest;
+
+mod common;
+
+use common::{test_state, TempPath};
+
+#[tokio::test]
+async fn test_sign_without_config_returns_428() {
+    // Prepare document
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Warehouse:002",
+            "type": "Warehouse",
+            "address": { "type": "Property", "value": { "foo": "bar" } }
+        }]
+    });
+
+    // Call sign endpoint without config
+    let (state, _key_store) = test_state("no-config");
+    let response = sign_handler(State(state), Query(SignQuery::default()), Json(doc.clone())).await;
+    match response {
+        Err(response) => assert_eq!(response.status().as_u16(), 428),
+        Ok(_) => panic!("Expected 428, got OK"),
+    }
+}
+
+#[tokio::test]
+async fn test_config_all_properties_signs_everything() {
+    let (state, _key_store) = test_state("all-properties");
+
+    // Save config with empty properties_to_sign => signs all object fields
+    let cfg = ConfigRequest {
+        entity_type: "Store".to_string(),
+        properties_to_sign: vec![],
+        ..Default::default()
+    };
+    let status = config_handler(State(state.clone()), Json(cfg)).await;
+    assert_eq!(status.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Store:002",
+            "type": "Store",
+            "address": { "type": "Property", "value": { "foo": "bar" } },
+            "location": { "type": "GeoProperty", "value": { "lat": 1, "lon": 2 } }
+        }]
+    });
+
+    let signed = sign_handler(State(state), Query(SignQuery::default()), Json(doc.clone())).await.unwrap().0;
+    let address = &signed["data"][0]["address"];
+    let location = &signed["data"][0]["location"];
+    assert!(address.get("ngsildproof").is_some(), "address not signed");
+    assert!(location.get("ngsildproof").is_some(), "location not signed");
+}
+
+#[tokio::test]
+async fn test_config_selective_signing() {
+    let (state, _key_store) = test_state("selective");
+
+    // Save config that only signs "address"
+    let cfg = ConfigRequest {
+        entity_type: "Building".to_string(),
+        properties_to_sign: vec!["address".to_string()],
+        ..Default::default()
+    };
+    let status = config_handler(State(state.clone()), Json(cfg)).await;
+    assert_eq!(status.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Building:002",
+            "type": "Building",
+            "address": { "type": "Property", "value": { "foo": "bar" } },
+            "location": { "type": "GeoProperty", "value": { "lat": 1, "lon": 2 } }
+        }]
+    });
+
+    let signed = sign_handler(State(state), Query(SignQuery::default()), Json(doc.clone())).await.unwrap().0;
+    let address = &signed["data"][0]["address"];
+    let location = &signed["data"][0]["location"];
+    assert!(address.get("ngsildproof").is_some(), "address not signed");
+    assert!(location.get("ngsildproof").is_none(), "location should not be signed");
+}
+
+#[tokio::test]
+async fn test_config_matches_expanded_types_and_attributes() {
+    use signer::handlers::verify::{verify_handler, VerificationStatus};
+
+    let (state, _key_store) = test_state("expanded");
+
+    let cfg = ConfigRequest {
+        entity_type: "Fountain".to_string(),
+        properties_to_sign: vec!["flow".to_string(), "flow.measuredBy".to_string()],
+        context: Some(json!({
+            "Fountain": "https://example.org/ns#Fountain",
+            "flow": "https://example.org/ns#flow",
+            "measuredBy": "https://example.org/ns#measuredBy"
+        })),
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+
+    // The same entity type and attributes, written with full IRIs and with a prefix
+    let full_iris = json!({
+        "id": "urn:ngsi-ld:Fountain:001",
+        "type": "https://example.org/ns#Fountain",
+        "https://example.org/ns#flow": {
+            "type": "Property",
+            "value": 12,
+            "https://example.org/ns#measuredBy": { "type": "Relationship", "object": "urn:ngsi-ld:Meter:001" }
+        },
+        "https://example.org/ns#pressure": { "type": "Property", "value": 2 }
+    });
+    let prefixed = json!({
+        "@context": { "ex": "https://example.org/ns#" },
+        "id": "urn:ngsi-ld:Fountain:002",
+        "type": "ex:Fountain",
+        "ex:flow": {
+            "type": "Property",
+            "value": 12,
+            "ex:measuredBy": { "type": "Relationship", "object": "urn:ngsi-ld:Meter:001" }
+        },
+        "ex:pressure": { "type": "Property", "value": 2 }
+    });
+
+    for (entity, flow, measured_by, pressure) in [
+        (full_iris, "https://example.org/ns#flow", "https://example.org/ns#measuredBy", "https://example.org/ns#pressure"),
+        (prefixed, "ex:flow", "ex:measuredBy", "ex:pressure"),
+    ] {
+        let doc = json!({ "data": [entity] });
+        let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+        assert!(signed[flow]["ngsildproof"].is_object());
+        assert!(signed[flow][measured_by]["ngsildproof"].is_object());
+        assert!(signed[pressure].get("ngsildproof").is_none());
+
+        let entity_id = signed["id"].as_str().unwrap().to_string();
+        let request = json!({ "document": signed });
+        let results = verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0.results;
+        assert_eq!(results[&entity_id][flow].status, VerificationStatus::True);
+        assert_eq!(results[&entity_id][&format!("{}.{}", flow, measured_by)].status, VerificationStatus::True);
+    }
+
+    let unknown = ConfigRequest {
+        entity_type: "Fountain".to_string(),
+        context: Some(json!("https://example.org/unknown-context.jsonld")),
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(unknown)).await.as_u16(), 400);
+}
+
+#[tokio::test]
+async fn test_config_context_is_read_from_the_service_cache() {
+    let dir = TempPath::dir("config-contexts");
+    std::fs::create_dir_all(dir.join("example.org/contexts")).unwrap();
+    std::fs::write(
+        dir.join("example.org/contexts/silo.jsonld"),
+        r#"{ "@context": { "Silo": "https://example.org/ns#Silo", "level": "https://example.org/ns#level" } }"#,
+    ).unwrap();
+
+    let cfg = || ConfigRequest {
+        entity_type: "Silo".to_string(),
+        properties_to_sign: vec!["level".to_string()],
+        context: Some(json!("https://example.org/contexts/silo.jsonld")),
+        ..Default::default()
+    };
+    let (uncached, _uncached_key_store) = test_state("uncached-context");
+    assert_eq!(config_handler(State(uncached), Json(cfg())).await.as_u16(), 400);
+
+    let (state, _key_store) = test_state("cached-context");
+    let state = state.with_context_dir(&*dir);
+    assert_eq!(config_handler(State(state.clone()), Json(cfg())).await.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "@context": "https://example.org/contexts/silo.jsonld",
+            "id": "urn:ngsi-ld:Silo:001",
+            "type": "Silo",
+            "level": { "type": "Property", "value": 70 }
+        }]
+    });
+    let signed = sign_handler(State(state), Query(SignQuery::default()), Json(doc)).await.unwrap().0;
+    assert!(signed["data"][0]["level"]["ngsildproof"].is_object());
+}
@@ -0,0 +1,278 @@
+use axum::Json;
+use axum::extract::{Query, State};
+use serde_json::{json, Value};
+use signer::cryptosuite::Cryptosuite;
+use signer::handlers::config::{config_handler, ConfigRequest};
+use signer::handlers::{sign::{sign_handler, SignQuery}, verify::verify_handler, verify::VerificationStatus, verify::VerifyResult};
+use signer::jcs;
+use signer::state::AppState;
+
+mod common;
+
+use common::{test_state, verify};
+
+async fn verify_with_policy(state: &AppState, document: Value, policy: &str) -> VerifyResult {
+    let request = json!({ "document": document, "policy": policy });
+    verify_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await.0
+}
+
+#[test]
+fn test_jcs_canonical_form() {
+    // RFC 8785, section 3.2.2 and appendix B
+    let value: Value = serde_json::from_str(r#"{
+        "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001, -0],
+        "string": "€$\u000F\u000aA'B\"\\\\\"\/",
+        "literals": [null, true, false]
+    }"#).unwrap();
+
+    assert_eq!(
+        jcs::canonicalize(&value).unwrap(),
+        r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27,0],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+    );
+
+    // Members are sorted by UTF-16 code units, not by UTF-8 bytes
+    let value = json!({ "\u{e9}": 1, "\u{1f600}": 2, "\u{ff61}": 3 });
+    assert_eq!(jcs::canonicalize(&value).unwrap(), "{\"\u{e9}\":1,\"\u{1f600}\":2,\"\u{ff61}\":3}");
+
+    // Integers are only signed if a double holds them exactly, and are then
+    // written as ECMAScript does
+    let value = json!([9007199254740992_u64, -9007199254740992_i64, 1152921504606846976_u64]);
+    assert_eq!(jcs::canonicalize(&value).unwrap(), "[9007199254740992,-9007199254740992,1152921504606847000]");
+    for n in [json!(9007199254740993_u64), json!(-9007199254740993_i64), json!(i64::MAX), json!(u64::MAX)] {
+        assert!(matches!(jcs::canonicalize(&n), Err(jcs::JcsError::InexactNumber(_))), "{} was rounded", n);
+    }
+}
+
+#[tokio::test]
+async fn test_sign_and_verify_with_eddsa_jcs_2022() {
+    let (state, _key_store) = test_state("jcs");
+
+    let cfg = ConfigRequest {
+        entity_type: "Bike".to_string(),
+        properties_to_sign: vec!["status".to_string()],
+        cryptosuite: Cryptosuite::EddsaJcs2022,
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Bike:001",
+            "type": "Bike",
+            "status": { "type": "Property", "value": { "battery": 0.8, "locked": true } }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    let proof = &signed["status"]["ngsildproof"]["proof"];
+    assert_eq!(proof["cryptosuite"], "eddsa-jcs-2022");
+    assert!(proof.get("@context").is_none());
+
+    // Members serialized in another order still verify
+    let reordered: Value = serde_json::from_str(&format!(
+        r#"{{ "status": {{ "ngsildproof": {}, "value": {{ "locked": true, "battery": 0.80 }}, "type": "Property" }}, "type": "Bike", "id": "urn:ngsi-ld:Bike:001" }}"#,
+        signed["status"]["ngsildproof"]
+    )).unwrap();
+
+    let mut tampered = signed.clone();
+    tampered["status"]["value"]["locked"] = json!(false);
+
+    let mut relabelled = signed.clone();
+    relabelled["status"]["ngsildproof"]["proof"]["cryptosuite"] = json!("eddsa-rdfc-2022");
+
+    let mut unknown = signed.clone();
+    unknown["status"]["ngsildproof"]["proof"]["cryptosuite"] = json!("rot13-2025");
+
+    let expectations = [(signed, true), (reordered, true), (tampered, false), (relabelled, false), (unknown, false)];
+
+    for (document, expected) in expectations {
+        let results = verify(&state, document).await;
+        assert_eq!(matches!(results.get("status"), Some(VerificationStatus::True)), expected);
+    }
+}
+
+#[tokio::test]
+async fn test_sign_and_verify_with_ecdsa_p384() {
+    use signer::handlers::keys::{keys_handler, KeysQuery, VerificationMethodType};
+    use signer::keystore::KeyAlgorithm;
+    use signer::multikey;
+
+    let (state, _key_store) = test_state("ecdsa");
+
+    let cfg = ConfigRequest {
+        entity_type: "Meter".to_string(),
+        properties_to_sign: vec!["consumption".to_string()],
+        cryptosuite: Cryptosuite::EcdsaRdfc2019,
+        key_algorithm: Some(KeyAlgorithm::P384),
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Meter:001",
+            "type": "Meter",
+            "consumption": { "type": "Property", "value": 1520.5, "unitCode": "KWH" }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    let proof = &signed["consumption"]["ngsildproof"]["proof"];
+    assert_eq!(proof["cryptosuite"], "ecdsa-rdfc-2019");
+
+    // The P-384 key is created on first use, next to the Ed25519 key
+    let p384_key = state.key_store.read().unwrap().active_key_for(KeyAlgorithm::P384).unwrap().clone();
+    assert_eq!(proof["verificationMethod"], state.verification_method(&p384_key.id));
+
+    let keys = serde_json::to_value(keys_handler(State(state.clone()), Query(KeysQuery { method_type: VerificationMethodType::Multikey })).await.0).unwrap();
+    let published = keys["keys"].as_array().unwrap().iter()
+        .find(|k| k["id"] == proof["verificationMethod"])
+        .unwrap();
+    let multibase = published["publicKeyMultibase"].as_str().unwrap();
+    assert!(multibase.starts_with("z82"));
+    assert_eq!(multikey::decode(multibase), Some(p384_key.public_key()));
+
+    let mut tampered = signed.clone();
+    tampered["consumption"]["unitCode"] = json!("MWH");
+
+    let mut relabelled = signed.clone();
+    relabelled["consumption"]["ngsildproof"]["proof"]["cryptosuite"] = json!("eddsa-rdfc-2022");
+
+    for (document, expected) in [(signed, true), (tampered, false), (relabelled, false)] {
+        let results = verify(&state, document).await;
+        assert_eq!(matches!(results.get("consumption"), Some(VerificationStatus::True)), expected);
+    }
+}
+
+#[tokio::test]
+async fn test_config_rejects_key_algorithm_of_another_suite() {
+    use signer::keystore::KeyAlgorithm;
+
+    let (state, _key_store) = test_state("config-rejects-key-algorithm-of-another-suite");
+
+    let cfg = ConfigRequest {
+        entity_type: "Valve".to_string(),
+        properties_to_sign: vec![],
+        cryptosuite: Cryptosuite::EddsaJcs2022,
+        key_algorithm: Some(KeyAlgorithm::P256),
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 400);
+}
+
+#[tokio::test]
+async fn test_sign_and_verify_with_mldsa65() {
+    use signer::handlers::keys::{keys_handler, KeysQuery, VerificationMethodType};
+    use signer::keystore::KeyAlgorithm;
+    use signer::multikey;
+
+    let (state, _key_store) = test_state("mldsa");
+
+    let cfg = ConfigRequest {
+        entity_type: "Archive".to_string(),
+        properties_to_sign: vec!["provenance".to_string()],
+        cryptosuite: Cryptosuite::MlDsa65Rdfc2024,
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Archive:001",
+            "type": "Archive",
+            "provenance": { "type": "Property", "value": "station-12", "observedAt": "2025-03-01T10:00:00Z" }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    let proof = &signed["provenance"]["ngsildproof"]["proof"];
+    assert_eq!(proof["cryptosuite"], "mldsa65-rdfc-2024");
+
+    let mldsa_key = state.key_store.read().unwrap().active_key_for(KeyAlgorithm::MlDsa65).unwrap().clone();
+    assert_eq!(proof["verificationMethod"], state.verification_method(&mldsa_key.id));
+
+    let keys = serde_json::to_value(keys_handler(State(state.clone()), Query(KeysQuery { method_type: VerificationMethodType::Multikey })).await.0).unwrap();
+    let published = keys["keys"].as_array().unwrap().iter()
+        .find(|k| k["id"] == proof["verificationMethod"])
+        .unwrap();
+    let multibase = published["publicKeyMultibase"].as_str().unwrap();
+    assert_eq!(multikey::decode(multibase), Some(mldsa_key.public_key()));
+
+    let jwk = multikey::jwk(&mldsa_key.public_key());
+    assert_eq!(jwk["kty"], "AKP");
+    assert_eq!(multikey::decode_jwk(&jwk), Some(mldsa_key.public_key()));
+
+    let mut tampered = signed.clone();
+    tampered["provenance"]["value"] = json!("station-13");
+
+    let mut relabelled = signed.clone();
+    relabelled["provenance"]["ngsildproof"]["proof"]["cryptosuite"] = json!("eddsa-rdfc-2022");
+
+    for (document, expected) in [(signed, true), (tampered, false), (relabelled, false)] {
+        let results = verify(&state, document).await;
+        assert_eq!(matches!(results.get("provenance"), Some(VerificationStatus::True)), expected);
+    }
+}
+
+#[tokio::test]
+async fn test_hybrid_proof_set_with_policy() {
+    use signer::handlers::config::ProofSuite;
+
+    let (state, _key_store) = test_state("proof-set");
+
+    let cfg = ConfigRequest {
+        entity_type: "Logger".to_string(),
+        properties_to_sign: vec!["reading".to_string()],
+        additional_proofs: vec![ProofSuite { cryptosuite: Cryptosuite::MlDsa65Rdfc2024, key_algorithm: None }],
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Logger:001",
+            "type": "Logger",
+            "reading": { "type": "Property", "value": 17.2 }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    let proofs = signed["reading"]["ngsildproof"]["proof"].as_array().unwrap();
+    assert_eq!(proofs.len(), 2);
+    assert_eq!(proofs[0]["cryptosuite"], "eddsa-rdfc-2022");
+    assert_eq!(proofs[1]["cryptosuite"], "mldsa65-rdfc-2024");
+
+    let result = verify_with_policy(&state, signed.clone(), "all").await;
+    assert!(matches!(result.results["urn:ngsi-ld:Logger:001"].get("reading").map(|r| r.status), Some(VerificationStatus::True)));
+    let statuses: Vec<_> = result.proofs["urn:ngsi-ld:Logger:001"]["reading"].iter().map(|p| p.status).collect();
+    assert_eq!(statuses, vec![VerificationStatus::True, VerificationStatus::True]);
+
+    // A broken post-quantum proof fails "all" but still satisfies "any"
+    let mut broken = signed.clone();
+    broken["reading"]["ngsildproof"]["proof"][1]["proofValue"] = proofs[0]["proofValue"].clone();
+
+    let result = verify_with_policy(&state, broken.clone(), "all").await;
+    assert!(matches!(result.results["urn:ngsi-ld:Logger:001"].get("reading").map(|r| r.status), Some(VerificationStatus::False)));
+    let statuses: Vec<_> = result.proofs["urn:ngsi-ld:Logger:001"]["reading"].iter().map(|p| p.status).collect();
+    assert_eq!(statuses, vec![VerificationStatus::True, VerificationStatus::False]);
+    assert_eq!(result.proofs["urn:ngsi-ld:Logger:001"]["reading"][1].cryptosuite.as_deref(), Some("mldsa65-rdfc-2024"));
+
+    let result = verify_with_policy(&state, broken, "any").await;
+    assert!(matches!(result.results["urn:ngsi-ld:Logger:001"].get("reading").map(|r| r.status), Some(VerificationStatus::True)));
+
+    // Tampering with the attribute breaks every proof of the set
+    let mut tampered = signed.clone();
+    tampered["reading"]["value"] = json!(17.3);
+    let result = verify_with_policy(&state, tampered, "any").await;
+    assert!(matches!(result.results["urn:ngsi-ld:Logger:001"].get("reading").map(|r| r.status), Some(VerificationStatus::False)));
+
+    // bbs-2023 proofs cannot be combined with others
+    let cfg = ConfigRequest {
+        entity_type: "Logger".to_string(),
+        properties_to_sign: vec![],
+        cryptosuite: Cryptosuite::Bbs2023,
+        additional_proofs: vec![ProofSuite { cryptosuite: Cryptosuite::MlDsa65Rdfc2024, key_algorithm: None }],
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 400);
+}
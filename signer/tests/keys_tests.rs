@@ -0,0 +1,179 @@
+use axum::Json;
+use axum::extract::{Query, State};
+use serde_json::json;
+use signer::handlers::config::{config_handler, ConfigRequest};
+use signer::handlers::keys::{did_document_handler, keys_handler, KeysQuery, VerificationMethodType};
+use signer::handlers::sign::{sign_handler, SignQuery};
+use signer::keystore::{KeyStatus, KeyStore};
+use signer::state::AppState;
+
+mod common;
+
+use common::TempPath;
+
+fn test_state(name: &str) -> (AppState, TempPath) {
+    let (state, key_store) = common::test_state(name);
+    (state.with_issuer("did:web:signer.example.org"), key_store)
+}
+
+#[tokio::test]
+async fn test_proof_points_to_published_key() {
+    let (state, _key_store) = test_state("published");
+
+    let cfg = ConfigRequest {
+        entity_type: "Vehicle".to_string(),
+        properties_to_sign: vec!["speed".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Vehicle:001",
+            "type": "Vehicle",
+            "speed": { "type": "Property", "value": 42 }
+        }]
+    });
+
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0;
+    let method = signed["data"][0]["speed"]["ngsildproof"]["proof"]["verificationMethod"].clone();
+
+    let keys = keys_handler(State(state.clone()), Query(KeysQuery { method_type: VerificationMethodType::Multikey })).await.0;
+    let published = serde_json::to_value(&keys).unwrap();
+
+    assert_eq!(published["keys"][0]["id"], method);
+    assert_eq!(published["keys"][0]["status"], "active");
+    assert!(published["keys"][0]["publicKeyMultibase"].as_str().unwrap().starts_with("z6Mk"));
+
+    let document = did_document_handler(State(state), Query(KeysQuery { method_type: VerificationMethodType::JsonWebKey2020 })).await.0;
+    assert_eq!(document["id"], "did:web:signer.example.org");
+    assert_eq!(document["assertionMethod"][0], method);
+    assert_eq!(document["verificationMethod"][0]["publicKeyJwk"]["crv"], "Ed25519");
+}
+
+#[tokio::test]
+async fn test_rotation_keeps_retired_key_verifiable() {
+    use signer::handlers::admin::{activate_key_handler, create_key_handler, CreateKeyRequest};
+    use signer::handlers::verify::{verify_handler, VerificationStatus, VerifyRequest};
+    use axum::extract::Path;
+    use axum::http::{header, HeaderMap, StatusCode};
+
+    let (state, _key_store) = test_state("rotation");
+    let state = state.with_admin_token("s3cret");
+
+    let cfg = ConfigRequest {
+        entity_type: "Parking".to_string(),
+        properties_to_sign: vec!["occupancy".to_string()],
+        ..Default::default()
+    };
+    config_handler(State(state.clone()), Json(cfg)).await;
+
+    // Key administration is disabled until an admin token is configured
+    let (disabled, _disabled_key_store) = test_state("rotation-disabled");
+    let response = create_key_handler(State(disabled), HeaderMap::new(), Json(CreateKeyRequest::default())).await.err().unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let bearer = |token: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    };
+
+    for headers in [HeaderMap::new(), bearer("guess")] {
+        let response = create_key_handler(State(state.clone()), headers, Json(CreateKeyRequest::default())).await.err().unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = activate_key_handler(State(state.clone()), bearer("guess"), Path("key-1".into())).await.err().unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Parking:001",
+            "type": "Parking",
+            "occupancy": { "type": "Property", "value": 0.5 }
+        }]
+    });
+    let signed_before = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc.clone())).await.unwrap().0["data"][0].clone();
+
+    let (_, created) = create_key_handler(State(state.clone()), bearer("s3cret"), Json(CreateKeyRequest::default())).await.unwrap();
+    let new_id = created.0.method.id.rsplit('#').next().unwrap().to_string();
+    let activated = activate_key_handler(State(state.clone()), bearer("s3cret"), Path(new_id)).await.unwrap().0;
+    assert_eq!(activated.status, KeyStatus::Active);
+
+    let signed_after = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+    assert_ne!(
+        signed_before["occupancy"]["ngsildproof"]["proof"]["verificationMethod"],
+        signed_after["occupancy"]["ngsildproof"]["proof"]["verificationMethod"]
+    );
+
+    for signed in [signed_before, signed_after] {
+        let request = VerifyRequest { document: signed, ..Default::default() };
+        let results = verify_handler(State(state.clone()), Json(request)).await.0.results;
+        assert!(matches!(results["urn:ngsi-ld:Parking:001"].get("occupancy").map(|r| r.status), Some(VerificationStatus::True)));
+    }
+
+    let keys = serde_json::to_value(keys_handler(State(state), Query(KeysQuery { method_type: VerificationMethodType::Multikey })).await.0).unwrap();
+    assert_eq!(keys["keys"][0]["status"], "retired");
+    assert!(keys["keys"][0]["verifiableUntil"].is_string());
+    assert_eq!(keys["keys"][1]["status"], "active");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_key_store_is_saved_atomically_for_the_owner_only() {
+    use signer::keystore::KeyAlgorithm;
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = TempPath::new("atomic.json");
+    let temp_path = path.with_extension("json.tmp");
+    // A temporary file left over by a crash is replaced, whatever its mode
+    std::fs::write(&temp_path, "partial").unwrap();
+    std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    let mut key_store = KeyStore::load_or_generate(&*path).unwrap();
+    key_store.create_key(KeyAlgorithm::P256).unwrap();
+
+    assert_eq!(std::fs::metadata(&*path).unwrap().permissions().mode() & 0o777, 0o600);
+    assert!(!temp_path.exists());
+    assert_eq!(KeyStore::load_or_generate(&*path).unwrap().keys().len(), 2);
+}
+
+#[test]
+fn test_key_store_is_unchanged_when_it_cannot_be_saved() {
+    use signer::keystore::KeyAlgorithm;
+
+    let dir = TempPath::dir("unsaved");
+    let path = dir.join("keys.json");
+    let mut key_store = KeyStore::load_or_generate(&path).unwrap();
+    let pending = key_store.create_key(KeyAlgorithm::Ed25519).unwrap().id.clone();
+
+    // A directory where the temporary file goes makes every save fail
+    std::fs::create_dir(dir.join("keys.json.tmp")).unwrap();
+    assert!(key_store.create_key(KeyAlgorithm::P256).is_err());
+    assert!(key_store.activate(&pending).is_err());
+
+    let statuses: Vec<_> = key_store.keys().iter().map(|k| (k.id.as_str(), k.status)).collect();
+    assert_eq!(statuses, vec![("key-1", KeyStatus::Active), ("key-2", KeyStatus::Pending)]);
+    assert_eq!(key_store.active_key().id, "key-1");
+}
+
+#[tokio::test]
+async fn test_retired_key_is_dropped_once_its_retention_is_over() {
+    use signer::keystore::KeyAlgorithm;
+
+    let path = TempPath::new("retention.json");
+    let mut key_store = KeyStore::load_or_generate(&*path).unwrap().with_retention(chrono::Duration::zero());
+    let active = key_store.create_key(KeyAlgorithm::Ed25519).unwrap().id.clone();
+    key_store.activate(&active).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+
+    assert_eq!(key_store.keys().len(), 2);
+    assert_eq!(key_store.verifiable_keys().map(|k| k.id.as_str()).collect::<Vec<_>>(), vec![active.as_str()]);
+    assert!(key_store.verifiable_key("key-1").is_none());
+
+    let state = AppState::new(key_store).with_issuer("did:web:signer.example.org");
+    let keys = serde_json::to_value(keys_handler(State(state), Query(KeysQuery { method_type: VerificationMethodType::Multikey })).await.0).unwrap();
+    assert_eq!(keys["keys"].as_array().unwrap().len(), 1);
+    assert_eq!(keys["keys"][0]["id"], format!("did:web:signer.example.org#{}", active));
+    assert_eq!(keys["keys"][0]["status"], "active");
+}
@@ -0,0 +1,72 @@
+use ed25519_dalek::SigningKey;
+use serde_json::json;
+use signer::multikey;
+use signer::resolver::{DidKeyResolver, DidWebResolver, KeyResolver, PublicKey, TrustedKeysResolver};
+
+mod common;
+
+use common::TempPath;
+
+#[test]
+fn test_did_key_resolution() {
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    let multibase = multikey::encode_ed25519(&signing_key.verifying_key());
+
+    let did = format!("did:key:{}", multibase);
+    let method = format!("{}#{}", did, multibase);
+    let resolver = DidKeyResolver::new([did, "did:key:zNotAKey".to_string()]);
+    assert_eq!(resolver.resolve(&method), Some(PublicKey::Ed25519(signing_key.verifying_key())));
+    assert_eq!(resolver.resolve("did:key:zNotAKey#zNotAKey"), None);
+
+    // Anyone can mint a did:key, so those outside the allowlist do not resolve
+    let other = multikey::encode_ed25519(&SigningKey::from_bytes(&[6u8; 32]).verifying_key());
+    assert_eq!(resolver.resolve(&format!("did:key:{}#{}", other, other)), None);
+    assert_eq!(DidKeyResolver::new(Vec::<String>::new()).resolve(&method), None);
+}
+
+#[test]
+fn test_did_web_resolution_from_local_documents() {
+    let signing_key = SigningKey::from_bytes(&[8u8; 32]);
+    let root = TempPath::dir("did-web");
+
+    let document = json!({
+        "id": "did:web:partner.example%3A8443",
+        "verificationMethod": [{
+            "id": "#key-1",
+            "type": "JsonWebKey2020",
+            "controller": "did:web:partner.example%3A8443",
+            "publicKeyJwk": multikey::ed25519_jwk(&signing_key.verifying_key())
+        }]
+    });
+    let dir = root.join("partner.example:8443").join(".well-known");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("did.json"), document.to_string()).unwrap();
+
+    let resolver = DidWebResolver::new(&*root);
+    assert_eq!(
+        resolver.resolve("did:web:partner.example%3A8443#key-1"),
+        Some(PublicKey::Ed25519(signing_key.verifying_key()))
+    );
+    assert_eq!(resolver.resolve("did:web:partner.example%3A8443#key-2"), None);
+    assert_eq!(resolver.resolve("did:web:..#key-1"), None);
+}
+
+#[test]
+fn test_trusted_keys_file() {
+    let signing_key = SigningKey::from_bytes(&[9u8; 32]);
+    let dir = TempPath::dir("trusted");
+    let path = dir.join("trusted_keys.json");
+
+    let trusted = json!({
+        "verificationMethod": [{
+            "id": "https://gateway.example/keys#sensor-1",
+            "type": "Multikey",
+            "publicKeyMultibase": multikey::encode_ed25519(&signing_key.verifying_key())
+        }]
+    });
+    std::fs::write(&path, trusted.to_string()).unwrap();
+
+    let resolver = TrustedKeysResolver::load(&path).unwrap();
+    assert!(resolver.resolve("https://gateway.example/keys#sensor-1").is_some());
+    assert!(resolver.resolve("https://gateway.example/keys#sensor-2").is_none());
+}
@@ -0,0 +1,192 @@
+use axum::Json;
+use axum::extract::{Query, State};
+use serde_json::{json, Value};
+use signer::bbs;
+use signer::cryptosuite::Cryptosuite;
+use signer::handlers::config::{config_handler, ConfigRequest};
+use signer::handlers::derive::derive_handler;
+use signer::handlers::{sign::{sign_handler, SignQuery}, verify::VerificationStatus};
+use signer::state::AppState;
+
+mod common;
+
+use common::{test_state, verify};
+
+async fn derive(state: &AppState, document: &Value, pointers: &[&str]) -> Result<Value, u16> {
+    let request = json!({ "document": document, "selectivePointers": pointers });
+    derive_handler(State(state.clone()), Json(serde_json::from_value(request).unwrap())).await
+        .map(|json| json.0)
+        .map_err(|response| response.status().as_u16())
+}
+
+#[test]
+fn test_bbs_signature_and_proof_of_knowledge() {
+    let secret_key = bbs::SecretKey::random();
+    let public_key = secret_key.public_key();
+    let messages: Vec<Vec<u8>> = ["speed", "heading", "driver"].iter().map(|m| m.as_bytes().to_vec()).collect();
+
+    let signature = secret_key.sign(b"header", &messages).unwrap();
+    assert!(public_key.verify(b"header", &messages, &signature));
+    assert!(!public_key.verify(b"other header", &messages, &signature));
+
+    let proof = public_key.prove(&signature, b"header", b"nonce", &messages, &[0, 2]).unwrap();
+    let disclosed = vec![(0, messages[0].clone()), (2, messages[2].clone())];
+    assert!(public_key.verify_proof(&proof, b"header", b"nonce", &disclosed));
+    assert!(!public_key.verify_proof(&proof, b"header", b"replayed", &disclosed));
+    assert!(!public_key.verify_proof(&proof, b"header", b"nonce", &[(0, messages[0].clone()), (2, b"nobody".to_vec())]));
+
+    // Proofs are unlinkable: two proofs of the same signature differ
+    let again = public_key.prove(&signature, b"header", b"nonce", &messages, &[0, 2]).unwrap();
+    assert_ne!(proof, again);
+
+    let restored = bbs::SecretKey::from_bytes(&secret_key.to_bytes()).unwrap();
+    assert_eq!(restored.public_key(), public_key);
+    assert_eq!(bbs::PublicKey::from_bytes(&public_key.to_bytes()), Some(public_key));
+}
+
+#[test]
+fn test_bbs_message_count_is_bounded() {
+    let secret_key = bbs::SecretKey::random();
+    let public_key = secret_key.public_key();
+    let messages: Vec<Vec<u8>> = ["speed", "heading"].iter().map(|m| m.as_bytes().to_vec()).collect();
+
+    let signature = secret_key.sign(b"header", &messages).unwrap();
+    let mut proof = public_key.prove(&signature, b"header", b"nonce", &messages, &[0]).unwrap();
+    let disclosed = vec![(0, messages[0].clone())];
+    assert!(public_key.verify_proof(&proof, b"header", b"nonce", &disclosed));
+
+    // Each added scalar claims one more undisclosed message
+    let cp = proof.split_off(proof.len() - 32);
+    proof.extend(vec![1u8; bbs::MAX_MESSAGES * 32]);
+    proof.extend(cp);
+    assert!(!public_key.verify_proof(&proof, b"header", b"nonce", &disclosed));
+
+    let too_many = vec![b"reading".to_vec(); bbs::MAX_MESSAGES + 1];
+    assert_eq!(secret_key.sign(b"header", &too_many), Err(bbs::BbsError::TooManyMessages));
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+/// Fixtures of the `BLS12-381-SHA-256` ciphersuite in
+/// draft-irtf-cfrg-bbs-signatures, appendix "Test Vectors".
+#[test]
+fn test_bbs_bls12_381_sha_256_fixtures() {
+    let secret_key = bbs::SecretKey::generate(
+        &hex("746869732d49532d6a7573742d616e2d546573742d494b4d2d746f2d67656e65726174652d246528724074232d6b6579"),
+        &hex("746869732d49532d736f6d652d6b65792d6d657461646174612d746f2d62652d757365642d696e2d746573742d6b65792d67656e"),
+        Some(&hex("4242535f424c53313233383147315f584d443a5348412d3235365f535357555f524f5f4832475f484d32535f4b455947454e5f4453545f")),
+    ).unwrap();
+    assert_eq!(secret_key.to_bytes().to_vec(), hex("60e55110f76883a13d030b2f6bd11883422d5abde717569fc0731f51237169fc"));
+    let public_key = secret_key.public_key();
+    assert_eq!(public_key.to_bytes().to_vec(), hex("a820f230f6ae38503b86c70dc50b61c58a77e45c39ab25c0652bbaa8fa136f2851bd4781c9dcde39fc9d1d52c9e60268061e7d7632171d91aa8d460acee0e96f1e7c4cfb12d3ff9ab5d5dc91c277db75c845d649ef3c4f63aebc364cd55ded0c"));
+
+    let (p1, generators) = bbs::Generators::create(10).compressed();
+    assert_eq!(p1.to_vec(), hex("a8ce256102840821a3e94ea9025e4662b205762f9776b3a766c872b948f1fd225e7c59698588e70d11406d161b4e28c9"));
+    let expected = [
+        "a9ec65b70a7fbe40c874c9eb041c2cb0a7af36ccec1bea48fa2ba4c2eb67ef7f9ecb17ed27d38d27cdeddff44c8137be",
+        "98cd5313283aaf5db1b3ba8611fe6070d19e605de4078c38df36019fbaad0bd28dd090fd24ed27f7f4d22d5ff5dea7d4",
+        "a31fbe20c5c135bcaa8d9fc4e4ac665cc6db0226f35e737507e803044093f37697a9d452490a970eea6f9ad6c3dcaa3a",
+        "b479263445f4d2108965a9086f9d1fdc8cde77d14a91c856769521ad3344754cc5ce90d9bc4c696dffbc9ef1d6ad1b62",
+        "ac0401766d2128d4791d922557c7b4d1ae9a9b508ce266575244a8d6f32110d7b0b7557b77604869633bb49afbe20035",
+        "b95d2898370ebc542857746a316ce32fa5151c31f9b57915e308ee9d1de7db69127d919e984ea0747f5223821b596335",
+        "8f19359ae6ee508157492c06765b7df09e2e5ad591115742f2de9c08572bb2845cbf03fd7e23b7f031ed9c7564e52f39",
+        "abc914abe2926324b2c848e8a411a2b6df18cbe7758db8644145fefb0bf0a2d558a8c9946bd35e00c69d167aadf304c1",
+        "80755b3eb0dd4249cbefd20f177cee88e0761c066b71794825c9997b551f24051c352567ba6c01e57ac75dff763eaa17",
+        "82701eb98070728e1769525e73abff1783cedc364adb20c05c897a62f2ab2927f86f118dcb7819a7b218d8f3fee4bd7f",
+        "a1f229540474f4d6f1134761b92b788128c7ac8dc9b0c52d59493132679673032ac7db3fb3d79b46b13c1c41ee495bca",
+    ];
+    assert_eq!(generators.iter().map(|g| g.to_vec()).collect::<Vec<_>>(), expected.map(hex));
+
+    let header = hex("11223344556677889900aabbccddeeff");
+    let messages: Vec<Vec<u8>> = [
+        "9872ad089e452c7b6e283dfac2a80d58e8d0ff71cc4d5e310a1debdda4a45f02",
+        "c344136d9ab02da4dd5908bbba913ae6f58c2cc844b802a6f811f5fb075f9b80",
+        "7372e9daa5ed31e6cd5c825eac1b855e84476a1d94932aa348e07b73",
+        "77fe97eb97a1ebe2e81e4e3597a3ee740a66e9ef2412472c",
+        "496694774c5604ab1b2544eababcf0f53278ff50",
+        "515ae153e22aae04ad16f759e07237b4",
+        "d183ddc6e2665aa4e2f088af",
+        "ac55fb33a75909ed",
+        "96012096",
+        "",
+    ].map(hex).to_vec();
+
+    let single = secret_key.sign(&header, &messages[..1]).unwrap();
+    assert_eq!(single, hex("84773160b824e194073a57493dac1a20b667af70cd2352d8af241c77658da5253aa8458317cca0eae615690d55b1f27164657dcafee1d5c1973947aa70e2cfbb4c892340be5969920d0916067b4565a0"));
+    assert!(public_key.verify(&header, &messages[..1], &single));
+
+    let multi = secret_key.sign(&header, &messages).unwrap();
+    assert_eq!(multi, hex("8339b285a4acd89dec7777c09543a43e3cc60684b0a6f8ab335da4825c96e1463e28f8c5f4fd0641d19cec5920d3a8ff4bedb6c9691454597bbd298288abed3632078557b2ace7d44caed846e1a0a1e8"));
+    assert!(public_key.verify(&header, &messages, &multi));
+
+    // Single message proof, made with the draft's mocked random scalars
+    let ph = hex("bed231d880675ed101ead304512e043ade9958dd0241ea70b4b3957fba941501");
+    let proof = hex("94916292a7a6bade28456c601d3af33fcf39278d6594b467e128a3f83686a104ef2b2fcf72df0215eeaf69262ffe8194a19fab31a82ddbe06908985abc4c9825788b8a1610942d12b7f5debbea8985296361206dbace7af0cc834c80f33e0aadaeea5597befbb651827b5eed5a66f1a959bb46cfd5ca1a817a14475960f69b32c54db7587b5ee3ab665fbd37b506830a49f21d592f5e634f47cee05a025a2f8f94e73a6c15f02301d1178a92873b6e8634bafe4983c3e15a663d64080678dbf29417519b78af042be2b3e1c4d08b8d520ffab008cbaaca5671a15b22c239b38e940cfeaa5e72104576a9ec4a6fad78c532381aeaa6fb56409cef56ee5c140d455feeb04426193c57086c9b6d397d9418");
+    assert!(public_key.verify_proof(&proof, &header, &ph, &[(0, messages[0].clone())]));
+    assert!(!public_key.verify_proof(&proof, &header, &ph, &[(0, messages[1].clone())]));
+}
+
+#[tokio::test]
+async fn test_derive_discloses_selected_values_only() {
+    let (state, _key_store) = test_state("derive");
+
+    let cfg = ConfigRequest {
+        entity_type: "Parcel".to_string(),
+        properties_to_sign: vec![],
+        cryptosuite: Cryptosuite::Bbs2023,
+        ..Default::default()
+    };
+    assert_eq!(config_handler(State(state.clone()), Json(cfg)).await.as_u16(), 200);
+
+    let doc = json!({
+        "data": [{
+            "id": "urn:ngsi-ld:Parcel:001",
+            "type": "Parcel",
+            "temperature": { "type": "Property", "value": 4.5, "unitCode": "CEL", "observedAt": "2025-03-01T10:00:00Z" },
+            "address": { "type": "Property", "value": { "addressLocality": "Valencia", "streetAddress": "Calle Colón 1" } },
+            "location": { "type": "GeoProperty", "value": { "type": "Point", "coordinates": [-0.37, 39.47] } }
+        }]
+    });
+    let signed = sign_handler(State(state.clone()), Query(SignQuery::default()), Json(doc)).await.unwrap().0["data"][0].clone();
+
+    let proof = &signed["temperature"]["ngsildproof"]["proof"];
+    assert_eq!(proof["cryptosuite"], "bbs-2023");
+    assert!(proof["proofValue"].as_str().unwrap().starts_with('u'));
+
+    // Base proofs verify as issued
+    let results = verify(&state, signed.clone()).await;
+    for name in ["temperature", "address", "location"] {
+        assert!(matches!(results.get(name), Some(VerificationStatus::True)), "{}", name);
+    }
+
+    let derived = derive(&state, &signed, &["/temperature/value", "/address/value/addressLocality", "/location"]).await.unwrap();
+
+    assert_eq!(derived["temperature"]["value"], json!(4.5));
+    assert!(derived["temperature"].get("unitCode").is_none());
+    assert_eq!(derived["address"]["value"], json!({ "addressLocality": "Valencia" }));
+    assert_eq!(derived["location"]["value"]["coordinates"], json!([-0.37, 39.47]));
+
+    let results = verify(&state, derived.clone()).await;
+    for name in ["temperature", "address", "location"] {
+        assert!(matches!(results.get(name), Some(VerificationStatus::True)), "{}", name);
+    }
+
+    let mut tampered = derived.clone();
+    tampered["temperature"]["value"] = json!(2.5);
+    assert!(matches!(verify(&state, tampered).await.get("temperature"), Some(VerificationStatus::False)));
+
+    // Withheld values cannot be added back
+    let mut completed = derived.clone();
+    completed["temperature"]["unitCode"] = json!("CEL");
+    assert!(matches!(verify(&state, completed).await.get("temperature"), Some(VerificationStatus::False)));
+
+    // Unless selected, attributes are not disclosed at all
+    let derived = derive(&state, &signed, &["/temperature/observedAt"]).await.unwrap();
+    assert!(derived.get("address").is_none());
+    assert!(derived["temperature"].get("value").is_none());
+    assert!(matches!(verify(&state, derived).await.get("temperature"), Some(VerificationStatus::True)));
+
+    assert_eq!(derive(&state, &signed, &["/temperature/accuracy"]).await, Err(400));
+}
@@ -1,2 +0,0 @@
-mod api_tests;
-mod config_tests;